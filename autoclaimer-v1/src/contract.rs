@@ -1,56 +1,109 @@
 use crate::error::ContractError;
 #[cfg(test)]
 use crate::mocks::mock_functions::{
-    build_FIN_claim_msg, build_claim_msg, build_send_msg, build_stake_msg,
+    build_FIN_claim_msg, build_FIN_swap_msg, build_authz_msg_batch, build_claim_msg,
+    build_direct_claim_msg, build_send_msg, build_stake_msg, claim_msg_type, stake_msg_type,
 };
 #[cfg(not(test))]
-use common::claim::{build_FIN_claim_msg, build_claim_msg};
+use common::claim::{build_FIN_claim_msg, build_claim_msg, build_direct_claim_msg, claim_msg_type};
+#[cfg(not(test))]
+use common::common_functions::build_authz_msg_batch;
 #[cfg(not(test))]
 use common::send::build_send_msg;
 #[cfg(not(test))]
-use common::stake::build_stake_msg;
-use cw_storage_plus::Map;
+use common::stake::{build_stake_msg, stake_msg_type};
+#[cfg(not(test))]
+use common::swap::build_FIN_swap_msg;
+use cw_storage_plus::{Bound, KeyDeserialize, Map};
 
 use crate::msg::{
-    ConfigResponse, ExecuteMsg, GetSubscribedProtocolsResponse, GetSubscriptionsResponse,
-    InstantiateMsg, OldProtocolConfig, ProtocolConfig, ProtocolStrategy, ProtocolSubscriptionData,
-    QueryMsg, UpdateConfigMsg,
+    ClaimAndStakeResponseData, ClaimResponseData, ConfigResponse, DenomDriftResponse,
+    EffectiveFeeResponse, ExecuteMsg, FeeDestination, FinConfigData,
+    GetSubscribedProtocolsResponse, GetSubscriptionsResponse, InstantiateMsg, IsPausedResponse,
+    IsSubscribedResponse, NextClaimIdResponse, OldProtocolConfig, OverviewResponse,
+    PendingRewardsData, PreviewRewardsResponse, ProtocolConfig, ProtocolConfigResponse,
+    ProtocolCountResponse, ProtocolStrategy, ProtocolSubscriptionData, QueryMsg,
+    QuoteBatchFeesResponse, UpdateConfigMsg, ValidateProtocolConfigResponse,
 };
 use crate::state::{
-    Config, ExecutionData, CONFIG, PENDING_CLAIM_AND_STAKE_DATA, PENDING_CLAIM_ONLY_DATA,
-    PROTOCOL_CONFIG, SUBSCRIPTIONS, USER_EXECUTION_DATA,
+    Config, ExecutionData, PendingData, ACCRUED_FEES, CONFIG, DENOM_DRIFT, FAILED_FEES,
+    NEXT_CLAIM_ID, PENDING_ATOMIC_CLAIM_AND_STAKE_DATA, PENDING_CLAIM_AND_STAKE_DATA,
+    PENDING_CLAIM_ONLY_DATA, PENDING_FEE_DATA, PROTOCOL_CONFIG, PROTOCOL_COUNT, STATS,
+    SUBSCRIPTIONS, USER_EXECUTION_DATA,
 };
 
 use common::common_functions::query_token_balance;
+use common::events::{
+    action_event, add_debug_attribute, add_result, add_submsg_result, coins_received, ActionResult,
+};
+use common::staking_provider::StakingProvider;
 use cosmwasm_std::{
-    ensure, entry_point, to_json_binary, Addr, Binary, Deps, DepsMut, Env, Event, MessageInfo,
-    Reply, ReplyOn, Response, StdResult, SubMsg,
+    coin, ensure, entry_point, from_json, to_json_binary, Addr, Api, BankMsg, Binary, Coin,
+    Decimal, Deps, DepsMut, Empty, Env, MessageInfo, QueryRequest, Reply, ReplyOn, Response,
+    StdError, StdResult, Storage, SubMsg, Timestamp, Uint128, WasmQuery,
 };
-use cw_utils::nonpayable;
+use cw_utils::{nonpayable, parse_execute_response_data};
+use serde::{de::DeserializeOwned, Serialize};
 
-/// Enum representing the result of an action.
-#[derive(Debug, Clone, Copy)]
-enum ActionResult {
-    Ok,
-    Failed,
-}
-
-impl ActionResult {
-    fn as_str(&self) -> &'static str {
-        match self {
-            ActionResult::Ok => "ok",
-            ActionResult::Failed => "failed",
-        }
-    }
-}
+// Namespace of the events emitted by this contract (autorujira.autoclaimer)
+const EVENT_NAMESPACE: &str = "autoclaimer";
 
 // Constants for reply IDs
 const CLAIM_AND_STAKE_CLAIM_BASE_ID: u64 = 1000;
 const CLAIM_AND_STAKE_STAKE_BASE_ID: u64 = 2000;
 const CLAIM_AND_STAKE_SEND_BASE_ID: u64 = 3000;
 const CLAIM_ONLY_CLAIM_BASE_ID: u64 = 4000;
+const CLAIM_AND_SWAP_SWAP_BASE_ID: u64 = 5000;
+const CLAIM_AND_STAKE_STAKE_FEE_BASE_ID: u64 = 6000;
+const CLAIM_AND_STAKE_ATOMIC_BASE_ID: u64 = 7000;
+// Width of each reply ID range, a batch can't dispatch more messages than this
+const REPLY_ID_RANGE: u64 = 1000;
 const FEE_DIVISOR: u128 = 1_000_000_000_000_000_000u128;
 
+// Page sizes of the GetSubscriptions query
+const DEFAULT_SUBSCRIPTIONS_LIMIT: u32 = 10;
+const MAX_SUBSCRIPTIONS_LIMIT: u32 = 30;
+const DEFAULT_DELETE_PROTOCOL_LIMIT: u32 = 100;
+
+/// Helper function to compute the reply ID of the message at `index` in a batch.
+///
+/// # Arguments
+/// * `base` - The base ID of the reply range.
+/// * `index` - The position of the message in the batch.
+///
+/// # Returns
+/// A `Result<u64, ContractError>` with the reply ID, or an error if it would overflow
+/// into the next range.
+pub(crate) fn reply_id(base: u64, index: usize) -> Result<u64, ContractError> {
+    let index = index as u64;
+    ensure!(
+        index < REPLY_ID_RANGE,
+        ContractError::ReplyIdOverflow {
+            max_allowed: REPLY_ID_RANGE,
+        }
+    );
+    Ok(base + index)
+}
+
+/// Helper function to reject bank sends of denylisted denoms.
+///
+/// # Arguments
+/// * `storage` - Contract storage.
+/// * `denom` - The denom about to be sent.
+///
+/// # Returns
+/// A `Result<(), ContractError>` indicating success or failure.
+fn ensure_denom_sendable(storage: &dyn Storage, denom: &str) -> Result<(), ContractError> {
+    let config = CONFIG.load(storage)?;
+    ensure!(
+        !config.send_denylist.iter().any(|denied| denied == denom),
+        ContractError::DeniedDenom {
+            denom: denom.to_string(),
+        }
+    );
+    Ok(())
+}
+
 /// Helper function to validate protocols.
 ///
 /// # Arguments
@@ -70,6 +123,257 @@ fn validate_protocols(deps: &DepsMut, protocols: &Vec<String>) -> Result<(), Con
     Ok(())
 }
 
+/// Helper function to reject stale keeper submissions.
+///
+/// # Arguments
+/// * `env` - Information about the environment where the contract is running.
+/// * `deadline` - Optional latest block time at which the submission is still valid.
+///
+/// # Returns
+/// A `Result<(), ContractError>` indicating success or failure.
+fn validate_deadline(env: &Env, deadline: Option<Timestamp>) -> Result<(), ContractError> {
+    if let Some(deadline) = deadline {
+        ensure!(
+            env.block.time <= deadline,
+            ContractError::DeadlineExceeded { deadline }
+        );
+    }
+    Ok(())
+}
+
+/// Helper function to reject protocol configs that would fail at claim time.
+///
+/// # Arguments
+/// * `deps` - Dependencies used to validate addresses and query the configured contracts.
+/// * `protocol_config` - The protocol configuration to validate.
+///
+/// # Returns
+/// A `Result<(), ContractError>` listing every problem found on failure.
+fn validate_protocol_config(
+    deps: Deps,
+    protocol_config: &ProtocolConfig,
+) -> Result<(), ContractError> {
+    let errors = protocol_config_errors(deps, protocol_config);
+    ensure!(
+        errors.is_empty(),
+        ContractError::InvalidProtocolConfig {
+            protocol: protocol_config.protocol.clone(),
+            errors: errors.join("; "),
+        }
+    );
+    Ok(())
+}
+
+/// Collects the problems of a protocol config, shared by `UpdateConfig` and its dry run query.
+///
+/// # Arguments
+/// * `deps` - Dependencies used to validate addresses and query the configured contracts.
+/// * `protocol_config` - The protocol configuration to check.
+///
+/// # Returns
+/// The list of problems found, empty when the config is valid.
+fn protocol_config_errors(deps: Deps, protocol_config: &ProtocolConfig) -> Vec<String> {
+    let api = deps.api;
+    let mut errors = vec![];
+
+    if protocol_config.protocol.is_empty() {
+        errors.push("protocol must not be empty".to_string());
+    }
+
+    // Fees are bounded by the claimed amount
+    if protocol_config.fee_percentage > Decimal::one() {
+        errors.push("fee_percentage must not exceed 1".to_string());
+    }
+    for (denom, percentage) in &protocol_config.fee_percentages {
+        if *percentage > Decimal::one() {
+            errors.push(format!("fee percentage of {denom} must not exceed 1"));
+        }
+    }
+    if charges_fee(protocol_config) {
+        check_address(
+            api,
+            &mut errors,
+            "fee_address",
+            &protocol_config.fee_address,
+        );
+    }
+    if let FeeDestination::Stake { treasury } = &protocol_config.fee_destination {
+        check_address(api, &mut errors, "treasury", treasury.as_str());
+        if !matches!(
+            protocol_config.strategy,
+            ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards { .. }
+        ) {
+            errors.push("fee_destination Stake requires a claim and stake strategy".to_string());
+        }
+    }
+
+    if let Some(pending_rewards_query) = &protocol_config.pending_rewards_query {
+        check_query(&mut errors, "pending_rewards_query", pending_rewards_query);
+    }
+    if let Some(claim_window) = &protocol_config.claim_window {
+        if claim_window.period == 0 {
+            errors.push("claim_window period must not be zero".to_string());
+        } else if claim_window.start >= claim_window.period
+            || claim_window.end > claim_window.period
+            || claim_window.start == claim_window.end
+        {
+            errors.push("claim_window must be a non empty range within its period".to_string());
+        }
+    }
+
+    match &protocol_config.strategy {
+        ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+            provider,
+            claim_contract_address,
+            stake_contract_address,
+            reward_denom,
+            claim_id,
+            reward_denom_query,
+            atomic,
+        } => {
+            check_address(
+                api,
+                &mut errors,
+                "claim_contract_address",
+                claim_contract_address,
+            );
+            check_address(
+                api,
+                &mut errors,
+                "stake_contract_address",
+                stake_contract_address,
+            );
+            check_reward_denom(api, &mut errors, reward_denom, reward_denom_query);
+            check_claim_id(&mut errors, provider, claim_id);
+            if *atomic && protocol_config.pending_rewards_query.is_none() {
+                errors.push("atomic requires a pending_rewards_query".to_string());
+            }
+            if *atomic && protocol_config.direct_claim {
+                errors.push("atomic claims can not be direct claims".to_string());
+            }
+        }
+        ProtocolStrategy::ClaimAndSwapFIN {
+            provider,
+            claim_contract_address,
+            reward_denom,
+            fin_market,
+            target_denom,
+            claim_id,
+            reward_denom_query,
+            ..
+        } => {
+            check_address(
+                api,
+                &mut errors,
+                "claim_contract_address",
+                claim_contract_address,
+            );
+            check_address(api, &mut errors, "fin_market", fin_market);
+            check_reward_denom(api, &mut errors, reward_denom, reward_denom_query);
+            check_claim_id(&mut errors, provider, claim_id);
+            // A queried reward denom is only known at claim time
+            let static_reward_denom = reward_denom_query
+                .is_none()
+                .then_some(reward_denom)
+                .filter(|reward_denom| !reward_denom.is_empty());
+            if target_denom.is_empty() {
+                errors.push("target_denom must not be empty".to_string());
+            } else if static_reward_denom == Some(target_denom) {
+                errors.push("target_denom must differ from reward_denom".to_string());
+            } else if api.addr_validate(fin_market).is_ok() {
+                // The swap can only run on a market trading the reward denom for the target denom
+                match query_fin_config(deps, fin_market) {
+                    Ok(market_config) => {
+                        for (field, denom) in [
+                            ("reward_denom", static_reward_denom),
+                            ("target_denom", Some(target_denom)),
+                        ] {
+                            if let Some(denom) =
+                                denom.filter(|denom| !market_config.denoms.contains(denom))
+                            {
+                                errors.push(format!("fin_market does not trade {field} {denom}"));
+                            }
+                        }
+                    }
+                    Err(err) => errors.push(format!("fin_market config query failed: {err}")),
+                }
+            }
+        }
+        ProtocolStrategy::ClaimAndSend {
+            provider,
+            claim_contract_address,
+            reward_denom,
+            claim_id,
+            reward_denom_query,
+        } => {
+            check_address(
+                api,
+                &mut errors,
+                "claim_contract_address",
+                claim_contract_address,
+            );
+            check_reward_denom(api, &mut errors, reward_denom, reward_denom_query);
+            check_claim_id(&mut errors, provider, claim_id);
+        }
+        ProtocolStrategy::ClaimOnlyFIN { supported_markets } => {
+            for market in supported_markets {
+                check_address(api, &mut errors, "supported_markets", market);
+            }
+            if protocol_config.direct_claim {
+                errors.push("direct_claim is not supported by ClaimOnlyFIN".to_string());
+            }
+        }
+    }
+
+    errors
+}
+
+/// Records an error if `address` is not a valid address.
+fn check_address(api: &dyn Api, errors: &mut Vec<String>, field: &str, address: &str) {
+    if let Err(err) = api.addr_validate(address) {
+        errors.push(format!("{field} {address:?} is invalid: {err}"));
+    }
+}
+
+/// Records an error if a query template, with `{user}` substituted, is not valid json.
+fn check_query(errors: &mut Vec<String>, field: &str, query: &str) {
+    let query = query.replace("{user}", "user");
+    if serde_json::from_str::<serde_json::Value>(&query).is_err() {
+        errors.push(format!("{field} is not valid json"));
+    }
+}
+
+/// Records an error if the reward denom can be resolved neither statically nor by query.
+fn check_reward_denom(
+    api: &dyn Api,
+    errors: &mut Vec<String>,
+    reward_denom: &str,
+    reward_denom_query: &Option<(Addr, String)>,
+) {
+    match reward_denom_query {
+        Some((contract, query)) => {
+            check_address(
+                api,
+                errors,
+                "reward_denom_query contract",
+                contract.as_str(),
+            );
+            check_query(errors, "reward_denom_query", query);
+        }
+        None if reward_denom.is_empty() => {
+            errors.push("reward_denom must not be empty".to_string());
+        }
+        None => {}
+    }
+}
+
+/// Records an error if the provider needs a claim id and none is configured.
+fn check_claim_id(errors: &mut Vec<String>, provider: &StakingProvider, claim_id: &Option<u64>) {
+    if *provider == StakingProvider::DAO_DAO && claim_id.is_none() {
+        errors.push("claim_id is required by the DAO_DAO provider".to_string());
+    }
+}
+
 /// Initializes the contract and stores protocol configurations.
 ///
 /// Stores configurations such as `max_parallel_claims` and protocol settings.
@@ -92,17 +396,25 @@ pub fn instantiate(
     let config = Config {
         owner: msg.owner,
         max_parallel_claims: msg.max_parallel_claims,
+        min_claim_interval_seconds: msg.min_claim_interval_seconds,
+        paused: false,
+        max_subscriptions_per_user: msg.max_subscriptions_per_user,
+        max_protocols_per_user_per_call: msg.max_protocols_per_user_per_call,
+        fee_first: msg.fee_first,
+        track_failures: msg.track_failures,
+        send_denylist: msg.send_denylist,
+        require_subscription_for_claim_only: msg.require_subscription_for_claim_only,
+        purge_history_on_unsubscribe: msg.purge_history_on_unsubscribe,
+        pending_owner: None,
     };
 
     // Save the config in the state
     CONFIG.save(deps.storage, &config)?;
 
+    PROTOCOL_COUNT.save(deps.storage, &0)?;
     for protocol_config in msg.protocol_configs {
-        PROTOCOL_CONFIG.save(
-            deps.storage,
-            protocol_config.protocol.as_str(),
-            &protocol_config,
-        )?;
+        validate_protocol_config(deps.as_ref(), &protocol_config)?;
+        save_protocol_config(deps.storage, &protocol_config)?;
     }
 
     Ok(Response::new().add_attribute("action", "instantiate"))
@@ -111,27 +423,57 @@ pub fn instantiate(
 // Define the old Map with the same storage prefix
 const OLD_PROTOCOL_CONFIG: Map<&str, OldProtocolConfig> = Map::new("protocol_config");
 
+// Pending reply data of older deployments, superseded by the per action pending maps.
+// Keys and values are only handled raw, as they are dropped without being decoded.
+const LEGACY_PENDING_USER_PROTOCOL: Map<&[u8], Empty> = Map::new("pending_user_protocol");
+
 #[entry_point]
 pub fn migrate(deps: DepsMut, _env: Env, _info: MessageInfo) -> StdResult<Response> {
     // Load the existing global configuration
     let old_config = CONFIG.load(deps.storage)?;
 
-    // Get all the keys from the old protocol config
+    // Get all the keys from the old protocol config, raw as entries may be in either format
     let keys: Vec<String> = OLD_PROTOCOL_CONFIG
-        .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .keys_raw(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(String::from_vec)
         .collect::<StdResult<Vec<_>>>()?;
 
     // Iterate over each key to migrate data
     for protocol in keys {
+        // Configs already on the strategy format only need the DAO_DAO claim ID backfilled
+        if let Ok(mut protocol_config) = PROTOCOL_CONFIG.load(deps.storage, &protocol) {
+            if let ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+                provider: StakingProvider::DAO_DAO,
+                claim_id,
+                ..
+            } = &mut protocol_config.strategy
+            {
+                if claim_id.is_none() {
+                    *claim_id = Some(2);
+                    PROTOCOL_CONFIG.save(deps.storage, &protocol, &protocol_config)?;
+                }
+            }
+            continue;
+        }
+
         // Load old data using the old map
         let old_data = OLD_PROTOCOL_CONFIG.load(deps.storage, &protocol)?;
 
+        // Preserve the claim ID that used to be hard-coded for DAO_DAO claims
+        let claim_id = match old_data.provider {
+            StakingProvider::DAO_DAO => Some(2),
+            StakingProvider::CW_REWARDS | StakingProvider::CW20_STAKE => None,
+        };
+
         // Construct the new strategy based on the old data
         let new_strategy = ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
             provider: old_data.provider,
             claim_contract_address: old_data.claim_contract_address,
             stake_contract_address: old_data.stake_contract_address,
             reward_denom: old_data.reward_denom,
+            claim_id,
+            reward_denom_query: None,
+            atomic: false,
         };
 
         // Create the new protocol configuration
@@ -139,7 +481,15 @@ pub fn migrate(deps: DepsMut, _env: Env, _info: MessageInfo) -> StdResult<Respon
             protocol: protocol.clone(),
             fee_percentage: old_data.fee_percentage,
             fee_address: old_data.fee_address,
+            fee_destination: FeeDestination::Send {},
             strategy: new_strategy,
+            min_claim_interval: None,
+            fee_percentages: vec![],
+            pending_rewards_query: None,
+            min_claim_reward: Uint128::zero(),
+            direct_claim: false,
+            claim_window: None,
+            min_claim_amount: None,
         };
 
         // Save the new configuration using the new map
@@ -149,7 +499,43 @@ pub fn migrate(deps: DepsMut, _env: Env, _info: MessageInfo) -> StdResult<Respon
     // Save the updated global configuration
     CONFIG.save(deps.storage, &old_config)?;
 
-    Ok(Response::new().add_attribute("action", "migrate_protocols"))
+    // Seed the protocol count from the stored protocols, whatever format they were migrated from
+    let protocol_count = PROTOCOL_CONFIG
+        .keys_raw(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .count() as u32;
+    PROTOCOL_COUNT.save(deps.storage, &protocol_count)?;
+
+    // Drain the legacy pending data, no reply can consume it anymore
+    let legacy_pending_keys: Vec<Vec<u8>> = LEGACY_PENDING_USER_PROTOCOL
+        .keys_raw(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect();
+    for key in &legacy_pending_keys {
+        LEGACY_PENDING_USER_PROTOCOL.remove(deps.storage, key);
+    }
+
+    // Older deployments kept claim and stake and claim only pending data as bare tuples under
+    // the claim only namespace, drop every entry that no reply can decode anymore
+    let undecodable_pending_ids: Vec<u64> = PENDING_CLAIM_ONLY_DATA
+        .keys_raw(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(u64::from_vec)
+        .collect::<StdResult<Vec<_>>>()?
+        .into_iter()
+        .filter(|id| PENDING_CLAIM_ONLY_DATA.load(deps.storage, *id).is_err())
+        .collect();
+    for id in &undecodable_pending_ids {
+        PENDING_CLAIM_ONLY_DATA.remove(deps.storage, *id);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate_protocols")
+        .add_attribute(
+            "legacy_pending_removed",
+            legacy_pending_keys.len().to_string(),
+        )
+        .add_attribute(
+            "undecodable_pending_removed",
+            undecodable_pending_ids.len().to_string(),
+        ))
 }
 
 /// Updates the configuration for the specified protocols.
@@ -173,9 +559,11 @@ pub fn update_config(
     let mut config = CONFIG.load(deps.storage)?;
     ensure!(config.owner == info.sender, ContractError::Unauthorized {});
 
-    // Update the owner if provided
+    // Update the owner if provided.
+    // Deprecated: a mistyped address locks the contract, use ProposeNewOwner and AcceptOwnership.
     if let Some(owner) = msg.owner {
         config.owner = owner;
+        config.pending_owner = None;
     }
 
     // Update the max parallel claims if provided
@@ -183,21 +571,196 @@ pub fn update_config(
         config.max_parallel_claims = max_parallel_claims;
     }
 
+    // Update the min claim interval if provided
+    if let Some(min_claim_interval_seconds) = msg.min_claim_interval_seconds {
+        config.min_claim_interval_seconds = min_claim_interval_seconds;
+    }
+
+    // Pause or resume the contract if provided
+    if let Some(paused) = msg.paused {
+        config.paused = paused;
+    }
+
+    // Update the max subscriptions per user if provided
+    if let Some(max_subscriptions_per_user) = msg.max_subscriptions_per_user {
+        config.max_subscriptions_per_user = max_subscriptions_per_user;
+    }
+
+    // Update the max protocols per user per call if provided
+    if let Some(max_protocols_per_user_per_call) = msg.max_protocols_per_user_per_call {
+        config.max_protocols_per_user_per_call = max_protocols_per_user_per_call;
+    }
+
+    // Update the fee ordering if provided
+    if let Some(fee_first) = msg.fee_first {
+        config.fee_first = fee_first;
+    }
+
+    // Update the failure tracking if provided
+    if let Some(track_failures) = msg.track_failures {
+        config.track_failures = track_failures;
+    }
+
+    // Update the send denylist if provided
+    if let Some(send_denylist) = msg.send_denylist {
+        config.send_denylist = send_denylist;
+    }
+
+    // Update the claim only subscription check if provided
+    if let Some(require_subscription_for_claim_only) = msg.require_subscription_for_claim_only {
+        config.require_subscription_for_claim_only = require_subscription_for_claim_only;
+    }
+
+    // Update the unsubscribe history purge if provided
+    if let Some(purge_history_on_unsubscribe) = msg.purge_history_on_unsubscribe {
+        config.purge_history_on_unsubscribe = purge_history_on_unsubscribe;
+    }
+
     CONFIG.save(deps.storage, &config)?;
 
     if let Some(protocol_configs) = msg.protocol_configs {
         for protocol_config in protocol_configs {
-            PROTOCOL_CONFIG.save(
-                deps.storage,
-                protocol_config.protocol.as_str(),
-                &protocol_config,
-            )?;
+            validate_protocol_config(deps.as_ref(), &protocol_config)?;
+            save_protocol_config(deps.storage, &protocol_config)?;
         }
     }
 
     Ok(Response::new().add_attribute("action", "update_config"))
 }
 
+/// Saves a protocol configuration, counting it if the protocol is new.
+///
+/// # Arguments
+/// * `storage` - The contract storage.
+/// * `protocol_config` - The protocol configuration to save.
+///
+/// # Returns
+/// A `StdResult<()>` indicating success or failure.
+fn save_protocol_config(
+    storage: &mut dyn Storage,
+    protocol_config: &ProtocolConfig,
+) -> StdResult<()> {
+    if !PROTOCOL_CONFIG.has(storage, &protocol_config.protocol) {
+        let count = PROTOCOL_COUNT.may_load(storage)?.unwrap_or_default();
+        PROTOCOL_COUNT.save(storage, &(count + 1))?;
+    }
+    PROTOCOL_CONFIG.save(storage, &protocol_config.protocol, protocol_config)
+}
+
+/// Removes a protocol from the configuration and from the subscriptions listing it.
+///
+/// The first call, without `start_after`, removes the config. At most `limit` subscriptions
+/// are visited per call; while some are left, the last visited address is returned in the
+/// `next_start_after` attribute and the call is repeated with it as `start_after`. Entries
+/// left with no protocols are removed. The protocol must not be added back before the prune
+/// is finished, or the subscriptions not visited yet would list it again.
+///
+/// # Arguments
+/// * `deps` - Mutable dependencies for contract state access.
+/// * `protocol` - The protocol to remove.
+/// * `start_after` - The address after which the subscription prune resumes.
+/// * `limit` - The maximum number of subscriptions to visit.
+///
+/// # Returns
+/// A `Result<Response, ContractError>` with the number of affected subscribers.
+pub fn delete_protocol(
+    deps: DepsMut,
+    protocol: String,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let configured = PROTOCOL_CONFIG.has(deps.storage, &protocol);
+    if start_after.is_none() {
+        ensure!(configured, ContractError::InvalidProtocol { protocol });
+
+        PROTOCOL_CONFIG.remove(deps.storage, &protocol);
+        let count = PROTOCOL_COUNT.may_load(deps.storage)?.unwrap_or_default();
+        let count = count.checked_sub(1).ok_or_else(|| {
+            StdError::generic_err("Protocol count out of sync with the stored protocols")
+        })?;
+        PROTOCOL_COUNT.save(deps.storage, &count)?;
+    } else {
+        // Resuming after the protocol was added back would drop the new subscriptions
+        ensure!(
+            !configured,
+            ContractError::GenericError {
+                msg: format!("Protocol {protocol} was added back, the prune can not resume"),
+            }
+        );
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_DELETE_PROTOCOL_LIMIT) as usize;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let visited: Vec<(Addr, Vec<String>)> = SUBSCRIPTIONS
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut affected_subscribers = 0;
+    for (user, protocols) in &visited {
+        if !protocols.contains(&protocol) {
+            continue;
+        }
+        affected_subscribers += 1;
+        let remaining: Vec<String> = protocols
+            .iter()
+            .filter(|subscribed| **subscribed != protocol)
+            .cloned()
+            .collect();
+        if remaining.is_empty() {
+            SUBSCRIPTIONS.remove(deps.storage, user);
+        } else {
+            SUBSCRIPTIONS.save(deps.storage, user, &remaining)?;
+        }
+    }
+
+    let mut event = action_event(EVENT_NAMESPACE, "delete_protocol")
+        .add_attribute("protocol", protocol)
+        .add_attribute("affected_subscribers", affected_subscribers.to_string());
+    if visited.len() == limit {
+        if let Some((last_user, _)) = visited.last() {
+            event = event.add_attribute("next_start_after", last_user.to_string());
+        }
+    }
+
+    Ok(Response::new().add_event(add_result(event, ActionResult::Ok)))
+}
+
+/// Runs the protocol config validation on every stored protocol.
+///
+/// Nothing is modified, failing protocols are reported with one `invalid_protocol_config`
+/// event each so they can be fixed with `update_config` or `delete_protocol`.
+///
+/// # Arguments
+/// * `deps` - Dependencies for contract state access.
+///
+/// # Returns
+/// A `Result<Response, ContractError>` with the validation report.
+pub fn revalidate_configs(deps: Deps) -> Result<Response, ContractError> {
+    let protocol_configs = PROTOCOL_CONFIG
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| item.map(|(_, config)| config))
+        .collect::<StdResult<Vec<ProtocolConfig>>>()?;
+
+    let mut invalid_events = vec![];
+    for protocol_config in &protocol_configs {
+        let errors = protocol_config_errors(deps, protocol_config);
+        if !errors.is_empty() {
+            invalid_events.push(
+                action_event(EVENT_NAMESPACE, "invalid_protocol_config")
+                    .add_attribute("protocol", &protocol_config.protocol)
+                    .add_attribute("errors", errors.join("; ")),
+            );
+        }
+    }
+
+    let event = action_event(EVENT_NAMESPACE, "revalidate_configs")
+        .add_attribute("checked_count", protocol_configs.len().to_string())
+        .add_attribute("invalid_count", invalid_events.len().to_string());
+
+    Ok(Response::new().add_event(event).add_events(invalid_events))
+}
+
 /// Executes contract logic based on the message received.
 ///
 /// Supports `ClaimAndStake`, `Subscribe`, and `Unsubscribe`.
@@ -225,9 +788,26 @@ pub fn execute(
         ExecuteMsg::UpdateConfig {
             config: update_config_msg,
         } => update_config(deps, env, info, update_config_msg),
-        ExecuteMsg::ClaimAndStake { users_protocols } => {
+        ExecuteMsg::ClaimAndStake {
+            users_protocols,
+            deadline,
+        } => {
             let config = CONFIG.load(deps.storage)?;
             ensure!(config.owner == info.sender, ContractError::Unauthorized {});
+            ensure!(!config.paused, ContractError::Paused {});
+            validate_deadline(&env, deadline)?;
+
+            // Validation: Fail fast on any user with too many protocols, before summing
+            if config.max_protocols_per_user_per_call > 0 {
+                if let Some((user, _)) = users_protocols.iter().find(|(_, protocols)| {
+                    protocols.len() > config.max_protocols_per_user_per_call as usize
+                }) {
+                    return Err(ContractError::TooManyProtocolsPerUser {
+                        user: user.clone(),
+                        max_allowed: config.max_protocols_per_user_per_call,
+                    });
+                }
+            }
 
             let mut total_protocol_count = 0;
             let users_protocols: Vec<(Addr, Vec<String>)> = users_protocols
@@ -251,9 +831,12 @@ pub fn execute(
         ExecuteMsg::ClaimOnly {
             protocol,
             users_contracts,
+            deadline,
         } => {
             let config = CONFIG.load(deps.storage)?;
             ensure!(config.owner == info.sender, ContractError::Unauthorized {});
+            ensure!(!config.paused, ContractError::Paused {});
+            validate_deadline(&env, deadline)?;
             if users_contracts.len() > config.max_parallel_claims as usize {
                 return Err(ContractError::TooManyMessages {
                     max_allowed: config.max_parallel_claims as usize,
@@ -267,16 +850,111 @@ pub fn execute(
             subscribe(deps, user, protocols)
         }
         ExecuteMsg::Unsubscribe { protocols } => {
-            validate_protocols(&deps, &protocols)?;
+            // Protocols may have been removed from the config since the user subscribed,
+            // so only malformed names are rejected here
+            if let Some(protocol) = protocols.iter().find(|p| p.trim().is_empty()) {
+                return Err(ContractError::InvalidProtocol {
+                    protocol: protocol.clone(),
+                });
+            }
             let user = info.sender;
             unsubscribe(deps, user, protocols)
         }
+        ExecuteMsg::PruneEmptySubscriptions { start_after, limit } => {
+            let config = CONFIG.load(deps.storage)?;
+            ensure!(config.owner == info.sender, ContractError::Unauthorized {});
+            let start_after = start_after
+                .map(|address| deps.api.addr_validate(&address))
+                .transpose()?;
+            prune_empty_subscriptions(deps, start_after, limit)
+        }
+        ExecuteMsg::WithdrawFees {
+            denom,
+            amount,
+            to_address,
+        } => {
+            let config = CONFIG.load(deps.storage)?;
+            ensure!(config.owner == info.sender, ContractError::Unauthorized {});
+            withdraw_fees(deps, denom, amount, to_address)
+        }
+        ExecuteMsg::RetryFees { users_protocols } => {
+            let config = CONFIG.load(deps.storage)?;
+            ensure!(config.owner == info.sender, ContractError::Unauthorized {});
+            ensure!(!config.paused, ContractError::Paused {});
+            if users_protocols.len() > config.max_parallel_claims as usize {
+                return Err(ContractError::TooManyMessages {
+                    max_allowed: config.max_parallel_claims as usize,
+                });
+            }
+            let users_protocols = users_protocols
+                .into_iter()
+                .map(|(user, protocol)| Ok((deps.api.addr_validate(&user)?, protocol)))
+                .collect::<Result<Vec<(Addr, String)>, ContractError>>()?;
+            retry_fees(deps, env, users_protocols)
+        }
+        ExecuteMsg::GcPending { older_than, limit } => {
+            let config = CONFIG.load(deps.storage)?;
+            ensure!(config.owner == info.sender, ContractError::Unauthorized {});
+            gc_pending(deps, older_than, limit)
+        }
+        ExecuteMsg::DeleteProtocol {
+            protocol,
+            start_after,
+            limit,
+        } => {
+            let config = CONFIG.load(deps.storage)?;
+            ensure!(config.owner == info.sender, ContractError::Unauthorized {});
+            let start_after = start_after
+                .map(|address| deps.api.addr_validate(&address))
+                .transpose()?;
+            delete_protocol(deps, protocol, start_after, limit)
+        }
+        ExecuteMsg::Offboard {} => {
+            let user = info.sender;
+            offboard_user(deps, user)
+        }
+        ExecuteMsg::OffboardUser { user } => {
+            let config = CONFIG.load(deps.storage)?;
+            ensure!(config.owner == info.sender, ContractError::Unauthorized {});
+            let user = deps.api.addr_validate(&user)?;
+            offboard_user(deps, user)
+        }
+        ExecuteMsg::RevalidateConfigs {} => {
+            let config = CONFIG.load(deps.storage)?;
+            ensure!(config.owner == info.sender, ContractError::Unauthorized {});
+            revalidate_configs(deps.as_ref())
+        }
+        ExecuteMsg::ProposeNewOwner { new_owner } => {
+            let mut config = CONFIG.load(deps.storage)?;
+            ensure!(config.owner == info.sender, ContractError::Unauthorized {});
+            let new_owner = deps.api.addr_validate(&new_owner)?;
+            config.pending_owner = Some(new_owner.clone());
+            CONFIG.save(deps.storage, &config)?;
+            let event = action_event(EVENT_NAMESPACE, "propose_new_owner")
+                .add_attribute("pending_owner", new_owner);
+            Ok(Response::new().add_event(add_result(event, ActionResult::Ok)))
+        }
+        ExecuteMsg::AcceptOwnership {} => {
+            let mut config = CONFIG.load(deps.storage)?;
+            ensure!(
+                config.pending_owner.as_ref() == Some(&info.sender),
+                ContractError::Unauthorized {}
+            );
+            config.owner = info.sender;
+            config.pending_owner = None;
+            CONFIG.save(deps.storage, &config)?;
+            let event = action_event(EVENT_NAMESPACE, "accept_ownership")
+                .add_attribute("owner", config.owner);
+            Ok(Response::new().add_event(add_result(event, ActionResult::Ok)))
+        }
     }
 }
 
 /// Claims rewards and stakes them for users across different protocols.
 ///
-/// Only processes pairs where users are subscribed, ignoring others.
+/// Only processes pairs where users are subscribed, ignoring others. Pairs claimed
+/// less than the protocol's minimum claim interval ago, or outside of the protocol
+/// claim window, are ignored as well.
 ///
 /// # Arguments
 /// * `deps` - Mutable dependencies for contract state access.
@@ -290,81 +968,273 @@ pub fn execute_claim_and_stake(
     env: Env,
     users_protocols: Vec<(Addr, Vec<String>)>,
 ) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
     let mut messages: Vec<SubMsg> = vec![];
     let mut ignored_pairs: Vec<(Addr, String)> = vec![];
+    let mut below_min_reward_pairs: Vec<(Addr, String)> = vec![];
+    let mut preview_failed_pairs: Vec<(Addr, String)> = vec![];
+
+    // Flatten and sort the pairs so the same batch always gets the same reply ids
+    let mut pairs: Vec<(Addr, String)> = users_protocols
+        .into_iter()
+        .flat_map(|(user, protocols)| {
+            protocols
+                .into_iter()
+                .map(move |protocol| (user.clone(), protocol))
+        })
+        .collect();
+    pairs.sort();
 
-    for (user, protocols) in users_protocols {
+    for (user, protocol) in pairs {
         let user_subscriptions = SUBSCRIPTIONS
             .may_load(deps.storage, &user)?
             .unwrap_or_default();
+        if !user_subscriptions.contains(&protocol) {
+            ignored_pairs.push((user.clone(), protocol.clone()));
+            continue;
+        }
 
-        for protocol in protocols {
-            if !user_subscriptions.contains(&protocol) {
+        // Deleted protocols stay in the subscriptions until pruned
+        let Some(protocol_config) = PROTOCOL_CONFIG.may_load(deps.storage, &protocol)? else {
+            ignored_pairs.push((user.clone(), protocol.clone()));
+            continue;
+        };
+
+        // Skip pairs still within their claim cooldown
+        let min_claim_interval = protocol_config
+            .min_claim_interval
+            .unwrap_or(config.min_claim_interval_seconds);
+        if min_claim_interval > 0 {
+            if let Some(last_autoclaim) = USER_EXECUTION_DATA
+                .may_load(deps.storage, (user.clone(), protocol.clone()))?
+                .and_then(|execution_data| execution_data.last_autoclaim)
+            {
+                if last_autoclaim.plus_seconds(min_claim_interval) > env.block.time {
+                    ignored_pairs.push((user.clone(), protocol.clone()));
+                    continue;
+                }
+            }
+        }
+
+        // Skip pairs outside of the protocol claim window
+        if let Some(claim_window) = &protocol_config.claim_window {
+            if !claim_window.contains(env.block.time) {
                 ignored_pairs.push((user.clone(), protocol.clone()));
                 continue;
             }
+        }
 
-            let protocol_config = PROTOCOL_CONFIG.may_load(deps.storage, &protocol)?.ok_or(
-                ContractError::InvalidProtocol {
-                    protocol: protocol.clone(),
-                },
-            )?;
+        // Direct claims can only claim the positions held by the contract itself
+        if protocol_config.direct_claim && user != env.contract.address {
+            ignored_pairs.push((user.clone(), protocol.clone()));
+            continue;
+        }
+
+        // Skip pairs whose previewed rewards are below the protocol threshold, when previewable
+        if !protocol_config.min_claim_reward.is_zero()
+            && protocol_config.pending_rewards_query.is_some()
+            && !matches!(
+                protocol_config.strategy,
+                ProtocolStrategy::ClaimOnlyFIN { .. }
+            )
+        {
+            // A failing preview only skips this pair, not the whole batch
+            let pending_rewards =
+                match query_preview_rewards(deps.as_ref(), user.clone(), protocol.clone()) {
+                    Ok(preview) => preview.amount,
+                    Err(_) => {
+                        preview_failed_pairs.push((user.clone(), protocol.clone()));
+                        ignored_pairs.push((user.clone(), protocol.clone()));
+                        continue;
+                    }
+                };
+            if pending_rewards < protocol_config.min_claim_reward {
+                below_min_reward_pairs.push((user.clone(), protocol.clone()));
+                ignored_pairs.push((user.clone(), protocol.clone()));
+                continue;
+            }
+        }
+
+        match protocol_config.strategy {
+            ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+                ref provider,
+                ref claim_contract_address,
+                ref stake_contract_address,
+                claim_id,
+                atomic: true,
+                ..
+            } => {
+                // Stake the previewed rewards net of fees in the same authz batch as the claim,
+                // a failing preview only skips this pair
+                let previewed =
+                    resolve_reward_denom(deps.as_ref(), &protocol_config).and_then(|denom| {
+                        query_preview_rewards(deps.as_ref(), user.clone(), protocol.clone())
+                            .map(|preview| (denom, preview.amount))
+                            .map_err(ContractError::from)
+                    });
+                let (reward_denom, pending_rewards) = match previewed {
+                    Ok(previewed) => previewed,
+                    Err(_) => {
+                        preview_failed_pairs.push((user.clone(), protocol.clone()));
+                        ignored_pairs.push((user.clone(), protocol.clone()));
+                        continue;
+                    }
+                };
+                let effective_fee =
+                    resolve_effective_fee(&protocol_config, Some(reward_denom.as_str()));
+                let amount_to_stake =
+                    pending_rewards - compute_fee(&effective_fee, pending_rewards);
+                if amount_to_stake.is_zero() {
+                    ignored_pairs.push((user.clone(), protocol.clone()));
+                    continue;
+                }
+
+                let balance_before =
+                    query_token_balance(deps.as_ref(), &user, reward_denom.clone())?;
+                let msg_id = reply_id(CLAIM_AND_STAKE_ATOMIC_BASE_ID, messages.len())?;
+
+                // Save pending protocol data for processing in the reply
+                PENDING_ATOMIC_CLAIM_AND_STAKE_DATA.save(
+                    deps.storage,
+                    msg_id,
+                    &PendingData {
+                        data: (
+                            user.clone(),
+                            protocol.clone(),
+                            balance_before,
+                            amount_to_stake,
+                        ),
+                        saved_at: env.block.time,
+                    },
+                )?;
+
+                let claim_id =
+                    load_next_claim_id(deps.storage, &user, &protocol_config)?.or(claim_id);
+
+                // Create a single message claiming and staking
+                let claim_and_stake_msg = build_authz_msg_batch(
+                    env.clone(),
+                    user.clone(),
+                    vec![
+                        claim_msg_type(
+                            provider.clone(),
+                            deps.api.addr_validate(claim_contract_address)?,
+                            claim_id,
+                        )?,
+                        stake_msg_type(
+                            provider.clone(),
+                            deps.api.addr_validate(stake_contract_address)?,
+                            amount_to_stake.u128(),
+                            reward_denom,
+                        )?,
+                    ],
+                )?;
+
+                messages.push(SubMsg {
+                    msg: claim_and_stake_msg,
+                    gas_limit: None,
+                    id: msg_id,
+                    reply_on: ReplyOn::Always,
+                });
+            }
+            ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+                ref provider,
+                ref claim_contract_address,
+                claim_id,
+                ..
+            }
+            | ProtocolStrategy::ClaimAndSwapFIN {
+                ref provider,
+                ref claim_contract_address,
+                claim_id,
+                ..
+            }
+            | ProtocolStrategy::ClaimAndSend {
+                ref provider,
+                ref claim_contract_address,
+                claim_id,
+                ..
+            } => {
+                let reward_denom = resolve_reward_denom(deps.as_ref(), &protocol_config)?;
+                let balance_before = query_token_balance(deps.as_ref(), &user, reward_denom)?;
+                let msg_id = reply_id(CLAIM_AND_STAKE_CLAIM_BASE_ID, messages.len())?;
+
+                // Save pending protocol data for processing in the reply
+                PENDING_CLAIM_AND_STAKE_DATA.save(
+                    deps.storage,
+                    msg_id,
+                    &PendingData {
+                        data: (user.clone(), protocol.clone(), balance_before),
+                        saved_at: env.block.time,
+                    },
+                )?;
+
+                let claim_contract_addr = deps.api.addr_validate(claim_contract_address)?;
+                let claim_id =
+                    load_next_claim_id(deps.storage, &user, &protocol_config)?.or(claim_id);
 
-            match protocol_config.strategy {
-                ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
-                    ref provider,
-                    ref claim_contract_address,
-                    stake_contract_address: _,
-                    ref reward_denom,
-                } => {
-                    let balance_before =
-                        query_token_balance(deps.as_ref(), &user, reward_denom.to_string())?;
-
-                    // Save pending protocol data for processing in the reply
-                    PENDING_CLAIM_AND_STAKE_DATA.save(
-                        deps.storage,
-                        CLAIM_AND_STAKE_CLAIM_BASE_ID + messages.len() as u64,
-                        &(user.clone(), protocol.clone(), balance_before),
-                    )?;
-
-                    let claim_contract_addr = deps.api.addr_validate(claim_contract_address)?;
-
-                    // Create claim message
-                    let claim_msg = build_claim_msg(
+                // Create claim message, sent by the contract itself for direct claims
+                let claim_msg = if protocol_config.direct_claim {
+                    build_direct_claim_msg(provider.clone(), claim_contract_addr, claim_id)?
+                } else {
+                    build_claim_msg(
                         env.clone(),
                         user.clone(),
                         provider.clone(),
                         claim_contract_addr,
-                        2, // Example claim ID
-                    )?;
+                        claim_id,
+                    )?
+                };
 
-                    let submsg = SubMsg {
-                        msg: claim_msg,
-                        gas_limit: None,
-                        id: CLAIM_AND_STAKE_CLAIM_BASE_ID + messages.len() as u64,
-                        reply_on: ReplyOn::Always,
-                    };
+                let submsg = SubMsg {
+                    msg: claim_msg,
+                    gas_limit: None,
+                    id: msg_id,
+                    reply_on: ReplyOn::Always,
+                };
 
-                    messages.push(submsg);
-                }
-                _ => {
-                    ignored_pairs.push((user.clone(), protocol.clone()));
-                }
+                messages.push(submsg);
+            }
+            _ => {
+                ignored_pairs.push((user.clone(), protocol.clone()));
             }
         }
     }
 
-    let event = Event::new("autorujira.autoclaimer")
-        .add_attribute("action", "execute_claim_and_stake")
-        .add_attribute("ignored_count", ignored_pairs.len().to_string())
-        .add_attribute("ignored_pairs", format!("{:?}", ignored_pairs));
+    let event = add_debug_attribute(
+        add_debug_attribute(
+            add_debug_attribute(
+                action_event(EVENT_NAMESPACE, "execute_claim_and_stake")
+                    .add_attribute("grantee", env.contract.address.to_string())
+                    .add_attribute("ignored_count", ignored_pairs.len().to_string()),
+                "ignored_pairs",
+                &ignored_pairs,
+            ),
+            "below_min_reward",
+            &below_min_reward_pairs,
+        ),
+        "preview_failed",
+        &preview_failed_pairs,
+    );
+
+    // Summarize the dispatch-time decisions, the reply results are not known yet
+    let data = ClaimAndStakeResponseData {
+        dispatched: messages.len() as u32,
+        ignored: ignored_pairs
+            .into_iter()
+            .map(|(user, protocol)| (user.to_string(), protocol))
+            .collect(),
+    };
 
-    Ok(Response::new().add_submessages(messages).add_event(event))
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_event(event)
+        .set_data(to_json_binary(&data)?))
 }
 
 /// Handles the response after any submessage has been processed.
 ///
-/// The type of action (claim, stake, send) is determined by the reply ID.
+/// The type of action (claim, stake, send, swap, stake_fee, claim_and_stake) is determined by the reply ID.
 /// Events for `ok` or `failed` results are emitted accordingly.
 ///
 /// # Arguments
@@ -381,9 +1251,16 @@ pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractEr
     } else if msg.id >= CLAIM_AND_STAKE_STAKE_BASE_ID && msg.id < CLAIM_AND_STAKE_SEND_BASE_ID {
         process_claim_and_stake_stake_reply(msg)
     } else if msg.id >= CLAIM_AND_STAKE_SEND_BASE_ID && msg.id < CLAIM_ONLY_CLAIM_BASE_ID {
-        process_claim_and_stake_send_reply(msg)
-    } else if msg.id >= CLAIM_ONLY_CLAIM_BASE_ID {
+        process_claim_and_stake_send_reply(deps, env, msg)
+    } else if msg.id >= CLAIM_ONLY_CLAIM_BASE_ID && msg.id < CLAIM_AND_SWAP_SWAP_BASE_ID {
         process_claim_only_claim_reply(deps, env, msg)
+    } else if msg.id >= CLAIM_AND_SWAP_SWAP_BASE_ID && msg.id < CLAIM_AND_STAKE_STAKE_FEE_BASE_ID {
+        process_claim_and_swap_swap_reply(msg)
+    } else if msg.id >= CLAIM_AND_STAKE_STAKE_FEE_BASE_ID && msg.id < CLAIM_AND_STAKE_ATOMIC_BASE_ID
+    {
+        process_claim_and_stake_stake_fee_reply(msg)
+    } else if msg.id >= CLAIM_AND_STAKE_ATOMIC_BASE_ID {
+        process_claim_and_stake_atomic_reply(deps, env, msg)
     } else {
         Err(ContractError::InvalidReplyId { id: msg.id })
     }
@@ -401,13 +1278,17 @@ pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractEr
 /// # Returns
 /// A `Result<Response, ContractError>` indicating success or failure.
 fn process_claim_and_stake_claim_reply(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     msg: Reply,
 ) -> Result<Response, ContractError> {
-    if let Some((user, protocol, balance_before)) =
-        PENDING_CLAIM_AND_STAKE_DATA.may_load(deps.storage, msg.id)?
+    if let Some((user, protocol, balance_before)) = PENDING_CLAIM_AND_STAKE_DATA
+        .may_load(deps.storage, msg.id)?
+        .map(|pending| pending.data)
     {
+        // The reply consumes its pending data
+        PENDING_CLAIM_AND_STAKE_DATA.remove(deps.storage, msg.id);
+
         let protocol_config = PROTOCOL_CONFIG.load(deps.storage, &protocol)?;
 
         let msg_id_str = msg.id.to_string();
@@ -418,19 +1299,35 @@ fn process_claim_and_stake_claim_reply(
 
         let mut submessages = vec![];
         let mut claim_result = ActionResult::Ok;
+        let mut extra_events = vec![];
 
         match msg.result {
-            cosmwasm_std::SubMsgResult::Ok(_) => {
-                let reward_denom = match &protocol_config.strategy {
-                    ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards { reward_denom, .. } => {
-                        reward_denom
+            cosmwasm_std::SubMsgResult::Ok(response) => {
+                let reward_denom = resolve_reward_denom(deps.as_ref(), &protocol_config)?;
+
+                // Report denoms paid by the claim that differ from the configured reward denom.
+                // They are left in the user balance, so operators need the amounts to reconcile.
+                for coin in coins_received(&response.events, user.as_str())? {
+                    if coin.denom == reward_denom {
+                        continue;
                     }
-                    _ => {
-                        return Err(ContractError::InvalidStrategy {
-                            strategy: protocol_config.strategy.as_str().to_string(),
-                        })
+                    if DENOM_DRIFT.may_load(deps.storage, &protocol)?.is_none() {
+                        DENOM_DRIFT.save(deps.storage, &protocol, &coin.denom)?;
                     }
-                };
+                    extra_events.push(
+                        action_event(EVENT_NAMESPACE, "denom_drift")
+                            .add_attribute("protocol", protocol.clone())
+                            .add_attribute("expected_denom", reward_denom.clone())
+                            .add_attribute("observed_denom", coin.denom.clone()),
+                    );
+                    extra_events.push(
+                        action_event(EVENT_NAMESPACE, "unhandled_reward")
+                            .add_attribute("protocol", protocol.clone())
+                            .add_attribute("address", user.to_string())
+                            .add_attribute("denom", coin.denom)
+                            .add_attribute("amount", coin.amount.to_string()),
+                    );
+                }
 
                 let balance_after =
                     query_token_balance(deps.as_ref(), &user, reward_denom.clone())?;
@@ -441,98 +1338,599 @@ fn process_claim_and_stake_claim_reply(
                     }
                 })?;
 
-                let fee_amount = amount_claimed
-                    .multiply_ratio(protocol_config.fee_percentage.atomics(), FEE_DIVISOR);
+                // Cross-check the balance diff with the amount reported by the claim contract
+                if let Some(reported_amount) = response.data.as_ref().and_then(parse_claim_data) {
+                    attributes.push(("reported_amount", reported_amount.to_string()));
+                    if reported_amount != amount_claimed {
+                        attributes.push(("amount_discrepancy", "true".to_string()));
+                    }
+                }
+
+                // Dust is left in the user wallet: nothing is staked, no fee is charged, and the
+                // cooldown is not started. The claim id was still used, so it advances.
+                if protocol_config
+                    .min_claim_amount
+                    .is_some_and(|min_claim_amount| amount_claimed < min_claim_amount)
+                {
+                    advance_claim_id(deps.storage, &user, &protocol_config)?;
+                    attributes.push(("token", reward_denom));
+                    attributes.push(("tokens_claimed", amount_claimed.to_string()));
+
+                    let event = add_result(
+                        action_event(EVENT_NAMESPACE, "claim").add_attribute("msg_id", msg_id_str),
+                        ActionResult::SkippedDust,
+                    )
+                    .add_attributes(attributes);
+                    return Ok(Response::new().add_event(event).add_events(extra_events));
+                }
 
-                let stake_amount = amount_claimed.checked_sub(fee_amount).map_err(|_| {
+                let effective_fee =
+                    resolve_effective_fee(&protocol_config, Some(reward_denom.as_str()));
+                let fee_amount = compute_fee(&effective_fee, amount_claimed);
+
+                let net_amount = amount_claimed.checked_sub(fee_amount).map_err(|_| {
                     ContractError::NoRewards {
-                        msg: "Stake amount is zero".to_string(),
+                        msg: "Net amount is zero".to_string(),
                     }
                 })?;
 
-                // Handle ClaimAndStakeDaoDaoCwRewards strategy
-                if let ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
-                    provider,
-                    stake_contract_address,
-                    ..
-                } = &protocol_config.strategy
-                {
-                    // Create stake message
-                    let stake_msg = build_stake_msg(
-                        env.clone(),
-                        user.clone(),
-                        provider.clone(),
-                        deps.api.addr_validate(stake_contract_address)?,
-                        stake_amount.u128(),
-                        reward_denom.clone(),
-                    )?;
-
-                    // Create send fee message if fee > 0
-                    if fee_amount > 0u128.into() {
-                        let send_msg = build_send_msg(
+                // Create send fee message if fee > 0
+                let mut fee_submessages = build_fee_submessages(
+                    deps.branch(),
+                    &env,
+                    &user,
+                    &protocol_config,
+                    coin(fee_amount.u128(), reward_denom.clone()),
+                    msg.id - CLAIM_AND_STAKE_CLAIM_BASE_ID,
+                )?;
+
+                // Add attributes for success
+                attributes.push(("token", reward_denom.to_string()));
+                attributes.push(("tokens_claimed", amount_claimed.to_string()));
+                attributes.push(("fee_to_charge", fee_amount.to_string()));
+
+                match &protocol_config.strategy {
+                    // Nothing is left to stake or swap, only the fee is sent
+                    _ if net_amount.is_zero() => {
+                        if !fee_amount.is_zero() {
+                            extra_events.push(
+                                action_event(EVENT_NAMESPACE, "fully_consumed_by_fee")
+                                    .add_attribute("protocol", protocol.clone())
+                                    .add_attribute("address", user.to_string())
+                                    .add_attribute("fee", fee_amount.to_string()),
+                            );
+                        }
+                    }
+                    ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+                        provider,
+                        stake_contract_address,
+                        ..
+                    } => {
+                        // Create stake message
+                        let stake_msg = build_stake_msg(
                             env.clone(),
                             user.clone(),
-                            deps.api.addr_validate(&protocol_config.fee_address)?,
-                            fee_amount.u128(),
+                            provider.clone(),
+                            deps.api.addr_validate(stake_contract_address)?,
+                            net_amount.u128(),
                             reward_denom.clone(),
                         )?;
 
                         submessages.push(SubMsg {
-                            msg: send_msg,
+                            msg: stake_msg,
                             gas_limit: None,
-                            id: CLAIM_AND_STAKE_SEND_BASE_ID + msg.id
+                            id: CLAIM_AND_STAKE_STAKE_BASE_ID + msg.id
                                 - CLAIM_AND_STAKE_CLAIM_BASE_ID,
                             reply_on: ReplyOn::Always,
                         });
+
+                        attributes.push(("tokens_to_stake", net_amount.to_string()));
                     }
+                    ProtocolStrategy::ClaimAndSwapFIN {
+                        fin_market,
+                        target_denom,
+                        min_price,
+                        ..
+                    } => {
+                        // Create swap message, FIN returns the target denom to the user
+                        let min_return = min_price.map(|min_price| net_amount.mul_floor(min_price));
+                        let swap_msg = build_FIN_swap_msg(
+                            env.clone(),
+                            user.clone(),
+                            deps.api.addr_validate(fin_market)?,
+                            net_amount.u128(),
+                            reward_denom.clone(),
+                            min_return,
+                        )?;
 
-                    // Add submessages
-                    submessages.push(SubMsg {
-                        msg: stake_msg,
-                        gas_limit: None,
-                        id: CLAIM_AND_STAKE_STAKE_BASE_ID + msg.id - CLAIM_AND_STAKE_CLAIM_BASE_ID,
-                        reply_on: ReplyOn::Always,
-                    });
+                        submessages.push(SubMsg {
+                            msg: swap_msg,
+                            gas_limit: None,
+                            id: CLAIM_AND_SWAP_SWAP_BASE_ID + msg.id
+                                - CLAIM_AND_STAKE_CLAIM_BASE_ID,
+                            reply_on: ReplyOn::Always,
+                        });
 
-                    // Add attributes for success
-                    attributes.push(("token", reward_denom.to_string()));
-                    attributes.push(("tokens_claimed", amount_claimed.to_string()));
-                    attributes.push(("fee_to_charge", fee_amount.to_string()));
-                    attributes.push(("tokens_to_stake", stake_amount.to_string()));
-                    attributes.push(("timestamp", env.block.time.seconds().to_string()));
-
-                    // Save last autoclaim
-                    let execution_data = ExecutionData {
-                        last_autoclaim: env.block.time,
-                    };
-
-                    USER_EXECUTION_DATA.save(
-                        deps.storage,
-                        (user.clone(), protocol_config.protocol.clone()),
-                        &execution_data,
-                    )?;
+                        attributes.push(("tokens_to_swap", net_amount.to_string()));
+                        attributes.push(("target_denom", target_denom.clone()));
+                    }
+                    // The claim already paid the user wallet, only the fee leaves it
+                    ProtocolStrategy::ClaimAndSend { .. } => {
+                        attributes.push(("tokens_to_user", net_amount.to_string()));
+                    }
+                    ProtocolStrategy::ClaimOnlyFIN { .. } => {}
+                }
+
+                // Submessages run in order. The fee and the net amount add up to the claimed
+                // amount, so the user balance covers both whichever runs first.
+                if CONFIG.load(deps.storage)?.fee_first {
+                    fee_submessages.append(&mut submessages);
+                    submessages = fee_submessages;
+                } else {
+                    submessages.append(&mut fee_submessages);
                 }
+
+                attributes.push(("timestamp", env.block.time.seconds().to_string()));
+
+                record_claim_success(deps.storage, &env, &user, &protocol_config)?;
             }
             cosmwasm_std::SubMsgResult::Err(err) => {
                 attributes.push(("error", err.clone()));
                 claim_result = ActionResult::Failed;
+                record_claim_failure(deps.storage, &env, &user, &protocol)?;
+            }
+        }
+
+        // Create a single event with attributes
+        let event = add_result(
+            action_event(EVENT_NAMESPACE, "claim").add_attribute("msg_id", msg_id_str),
+            claim_result,
+        )
+        .add_attributes(attributes);
+
+        // Return the final response with submessages and events
+        Ok(Response::new()
+            .add_submessages(submessages)
+            .add_event(event)
+            .add_events(extra_events))
+    } else {
+        Err(ContractError::InvalidReplyId { id: msg.id })
+    }
+}
+
+/// Resolves the reward denom of a claim strategy.
+///
+/// If the strategy sets a `reward_denom_query`, the denom is queried from that contract,
+/// otherwise the static `reward_denom` is used.
+///
+/// # Arguments
+/// * `deps` - Dependencies for contract state access.
+/// * `protocol_config` - The configuration of the protocol being claimed.
+///
+/// # Returns
+/// A `Result<String, ContractError>` with the reward denom.
+fn resolve_reward_denom(
+    deps: Deps,
+    protocol_config: &ProtocolConfig,
+) -> Result<String, ContractError> {
+    match &protocol_config.strategy {
+        ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+            reward_denom,
+            reward_denom_query,
+            ..
+        }
+        | ProtocolStrategy::ClaimAndSwapFIN {
+            reward_denom,
+            reward_denom_query,
+            ..
+        }
+        | ProtocolStrategy::ClaimAndSend {
+            reward_denom,
+            reward_denom_query,
+            ..
+        } => match reward_denom_query {
+            Some((contract, query)) => {
+                let denom: String = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                    contract_addr: contract.to_string(),
+                    msg: Binary::from(query.as_bytes()),
+                }))?;
+                ensure!(
+                    !denom.trim().is_empty(),
+                    ContractError::InvalidRewardDenom {
+                        contract: contract.to_string(),
+                    }
+                );
+                Ok(denom)
             }
+            None => Ok(reward_denom.clone()),
+        },
+        _ => Err(ContractError::InvalidStrategy {
+            strategy: protocol_config.strategy.as_str().to_string(),
+        }),
+    }
+}
+
+/// Loads the next DAO_DAO claim id of a user on a protocol.
+///
+/// # Arguments
+/// * `storage` - Contract storage.
+/// * `user` - The user claiming.
+/// * `protocol_config` - The configuration of the protocol being claimed.
+///
+/// # Returns
+/// The stored claim id, or the strategy `claim_id` if none is stored yet.
+/// `None` for providers that do not use claim ids.
+fn load_next_claim_id(
+    storage: &dyn Storage,
+    user: &Addr,
+    protocol_config: &ProtocolConfig,
+) -> StdResult<Option<u64>> {
+    match &protocol_config.strategy {
+        ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+            provider: StakingProvider::DAO_DAO,
+            claim_id,
+            ..
+        }
+        | ProtocolStrategy::ClaimAndSwapFIN {
+            provider: StakingProvider::DAO_DAO,
+            claim_id,
+            ..
+        }
+        | ProtocolStrategy::ClaimAndSend {
+            provider: StakingProvider::DAO_DAO,
+            claim_id,
+            ..
+        } => Ok(NEXT_CLAIM_ID
+            .may_load(storage, (user.clone(), protocol_config.protocol.clone()))?
+            .or(*claim_id)),
+        _ => Ok(None),
+    }
+}
+
+/// Resolves the fee charged on a protocol, fees do not depend on the user paying them.
+///
+/// This is the single place where fee rules are layered. Resolution order:
+/// 1. The protocol `fee_percentage` and `fee_address` are the base fee and destination.
+/// 2. A `fee_percentages` entry for the reward denom overrides the base percentage.
+/// 3. A `FeeDestination::Stake` fee goes to its treasury instead of `fee_address`.
+/// 4. Protocols that never charge a fee (see `charges_fee`) are exempt.
+///
+/// # Arguments
+/// * `protocol_config` - The configuration of the protocol being claimed.
+/// * `denom` - The denom the fee is charged in, if known.
+///
+/// # Returns
+/// The resolved `EffectiveFeeResponse`.
+fn resolve_effective_fee(
+    protocol_config: &ProtocolConfig,
+    denom: Option<&str>,
+) -> EffectiveFeeResponse {
+    let percentage = denom
+        .and_then(|denom| {
+            protocol_config
+                .fee_percentages
+                .iter()
+                .find(|(fee_denom, _)| fee_denom == denom)
+        })
+        .map(|(_, percentage)| *percentage)
+        .unwrap_or(protocol_config.fee_percentage);
+
+    EffectiveFeeResponse {
+        percentage,
+        exempt: !charges_fee(protocol_config),
+        destination: match &protocol_config.fee_destination {
+            FeeDestination::Send {} => protocol_config.fee_address.clone(),
+            FeeDestination::Stake { treasury } => treasury.to_string(),
+        },
+    }
+}
+
+/// Returns whether a protocol charges fees at all.
+///
+/// Zero-fee protocols (e.g. FIN) may leave `fee_address` empty, so every fee path must check
+/// this before validating or sending to the fee destination.
+fn charges_fee(protocol_config: &ProtocolConfig) -> bool {
+    !protocol_config.fee_percentage.is_zero()
+        || protocol_config
+            .fee_percentages
+            .iter()
+            .any(|(_, percentage)| !percentage.is_zero())
+}
+
+/// Computes the fee charged on a claimed amount according to the resolved fee rules.
+fn compute_fee(effective_fee: &EffectiveFeeResponse, amount_claimed: Uint128) -> Uint128 {
+    if effective_fee.exempt {
+        return Uint128::zero();
+    }
+
+    amount_claimed
+        .multiply_ratio(effective_fee.percentage.atomics(), FEE_DIVISOR)
+        .min(amount_claimed)
+}
+
+/// Extracts the amount reported by a claim contract from the reply data.
+///
+/// The data is wrapped in a `MsgExecuteContractResponse`, itself wrapped in a `MsgExecResponse`
+/// when the claim runs through authz, so up to two protobuf layers are unwrapped.
+///
+/// # Arguments
+/// * `data` - The reply data of the claim submessage.
+///
+/// # Returns
+/// The reported amount, or `None` if the data does not carry a `ClaimResponseData`.
+fn parse_claim_data(data: &Binary) -> Option<Uint128> {
+    let mut payload = data.to_vec();
+    for _ in 0..2 {
+        payload = parse_execute_response_data(&payload).ok()?.data?.to_vec();
+        if let Ok(claim_data) = from_json::<ClaimResponseData>(&payload) {
+            return Some(claim_data.amount);
+        }
+    }
+    None
+}
+
+/// Builds the submessages charging the fee of a claim, if any.
+///
+/// The fee is sent to the effective fee destination and, for `FeeDestination::Stake`,
+/// staked on behalf of the treasury right after.
+///
+/// # Arguments
+/// * `deps` - Mutable dependencies for contract state access.
+/// * `env` - Information about the environment where the contract is running.
+/// * `user` - The user paying the fee.
+/// * `protocol_config` - The configuration of the claimed protocol.
+/// * `fee` - The fee to charge.
+/// * `index` - The index of the claim in its batch, used to derive the reply ids.
+///
+/// # Returns
+/// A `Result<Vec<SubMsg>, ContractError>` with the fee submessages, empty when there is no fee.
+fn build_fee_submessages(
+    deps: DepsMut,
+    env: &Env,
+    user: &Addr,
+    protocol_config: &ProtocolConfig,
+    fee: Coin,
+    index: u64,
+) -> Result<Vec<SubMsg>, ContractError> {
+    let mut fee_submessages = vec![];
+    if !charges_fee(protocol_config) || fee.amount.is_zero() {
+        return Ok(fee_submessages);
+    }
+
+    ensure_denom_sendable(deps.storage, fee.denom.as_str())?;
+    let effective_fee = resolve_effective_fee(protocol_config, Some(fee.denom.as_str()));
+    let send_msg = build_send_msg(
+        env.clone(),
+        user.clone(),
+        deps.api.addr_validate(&effective_fee.destination)?,
+        fee.amount.u128(),
+        fee.denom.clone(),
+    )?;
+
+    let send_msg_id = CLAIM_AND_STAKE_SEND_BASE_ID + index;
+    PENDING_FEE_DATA.save(
+        deps.storage,
+        send_msg_id,
+        &PendingData {
+            data: (user.clone(), protocol_config.protocol.clone(), fee.amount),
+            saved_at: env.block.time,
+        },
+    )?;
+
+    fee_submessages.push(SubMsg {
+        msg: send_msg,
+        gas_limit: None,
+        id: send_msg_id,
+        reply_on: ReplyOn::Always,
+    });
+
+    // Stake the fee on behalf of the treasury once it received it
+    if let FeeDestination::Stake { treasury } = &protocol_config.fee_destination {
+        let ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+            provider,
+            stake_contract_address,
+            ..
+        } = &protocol_config.strategy
+        else {
+            return Err(ContractError::InvalidStrategy {
+                strategy: protocol_config.strategy.as_str().to_string(),
+            });
+        };
+
+        let stake_fee_msg = build_stake_msg(
+            env.clone(),
+            treasury.clone(),
+            provider.clone(),
+            deps.api.addr_validate(stake_contract_address)?,
+            fee.amount.u128(),
+            fee.denom,
+        )?;
+
+        fee_submessages.push(SubMsg {
+            msg: stake_fee_msg,
+            gas_limit: None,
+            id: CLAIM_AND_STAKE_STAKE_FEE_BASE_ID + index,
+            reply_on: ReplyOn::Always,
+        });
+    }
+
+    Ok(fee_submessages)
+}
+
+/// Records a successful claim: saves the last autoclaim time and advances the DAO_DAO claim id.
+///
+/// # Arguments
+/// * `storage` - Contract storage.
+/// * `env` - Information about the environment where the contract is running.
+/// * `user` - The user whose claim succeeded.
+/// * `protocol_config` - The configuration of the claimed protocol.
+///
+/// # Returns
+/// A `StdResult<()>` indicating success or failure.
+fn record_claim_success(
+    storage: &mut dyn Storage,
+    env: &Env,
+    user: &Addr,
+    protocol_config: &ProtocolConfig,
+) -> StdResult<()> {
+    record_claim_stats(storage, true)?;
+
+    // Save last autoclaim
+    let execution_data = ExecutionData {
+        last_autoclaim: Some(env.block.time),
+        last_failed_at: None,
+    };
+
+    USER_EXECUTION_DATA.save(
+        storage,
+        (user.clone(), protocol_config.protocol.clone()),
+        &execution_data,
+    )?;
+
+    advance_claim_id(storage, user, protocol_config)
+}
+
+/// Advances the DAO_DAO claim id of a user on a protocol now that it has been used.
+fn advance_claim_id(
+    storage: &mut dyn Storage,
+    user: &Addr,
+    protocol_config: &ProtocolConfig,
+) -> StdResult<()> {
+    if let Some(claim_id) = load_next_claim_id(storage, user, protocol_config)? {
+        NEXT_CLAIM_ID.save(
+            storage,
+            (user.clone(), protocol_config.protocol.clone()),
+            &(claim_id + 1),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Processes the reply for an atomic claim and stake message.
+///
+/// The claim and the stake of the previewed net amount ran in a single authz batch, so what is
+/// left of the claimed rewards in the user balance pays the fee.
+///
+/// # Arguments
+/// * `deps` - Mutable dependencies for contract state access.
+/// * `env` - Information about the environment where the contract is running.
+/// * `msg` - The reply message after the claim and stake execution.
+///
+/// # Returns
+/// A `Result<Response, ContractError>` indicating success or failure.
+fn process_claim_and_stake_atomic_reply(
+    mut deps: DepsMut,
+    env: Env,
+    msg: Reply,
+) -> Result<Response, ContractError> {
+    let Some((user, protocol, balance_before, amount_staked)) = PENDING_ATOMIC_CLAIM_AND_STAKE_DATA
+        .may_load(deps.storage, msg.id)?
+        .map(|pending| pending.data)
+    else {
+        return Err(ContractError::InvalidReplyId { id: msg.id });
+    };
+    PENDING_ATOMIC_CLAIM_AND_STAKE_DATA.remove(deps.storage, msg.id);
+    let protocol_config = PROTOCOL_CONFIG.load(deps.storage, &protocol)?;
+
+    let mut attributes = vec![
+        ("protocol", protocol.clone()),
+        ("address", user.to_string()),
+    ];
+    let mut submessages = vec![];
+    let mut claim_result = ActionResult::Ok;
+
+    match msg.result {
+        cosmwasm_std::SubMsgResult::Ok(_) => {
+            let reward_denom = resolve_reward_denom(deps.as_ref(), &protocol_config)?;
+            let balance_after = query_token_balance(deps.as_ref(), &user, reward_denom.clone())?;
+
+            let amount_left = balance_after.checked_sub(balance_before).map_err(|_| {
+                ContractError::NoRewards {
+                    msg: "No rewards claimed".to_string(),
+                }
+            })?;
+            let amount_claimed = amount_left + amount_staked;
+
+            let effective_fee =
+                resolve_effective_fee(&protocol_config, Some(reward_denom.as_str()));
+            let fee_amount = compute_fee(&effective_fee, amount_claimed).min(amount_left);
+
+            submessages = build_fee_submessages(
+                deps.branch(),
+                &env,
+                &user,
+                &protocol_config,
+                coin(fee_amount.u128(), reward_denom.clone()),
+                msg.id - CLAIM_AND_STAKE_ATOMIC_BASE_ID,
+            )?;
+
+            attributes.push(("token", reward_denom));
+            attributes.push(("tokens_claimed", amount_claimed.to_string()));
+            attributes.push(("tokens_staked", amount_staked.to_string()));
+            attributes.push(("fee_to_charge", fee_amount.to_string()));
+            attributes.push(("timestamp", env.block.time.seconds().to_string()));
+
+            record_claim_success(deps.storage, &env, &user, &protocol_config)?;
+        }
+        cosmwasm_std::SubMsgResult::Err(err) => {
+            attributes.push(("error", err));
+            claim_result = ActionResult::Failed;
+            record_claim_failure(deps.storage, &env, &user, &protocol)?;
         }
+    }
 
-        // Create a single event with attributes
-        let event = Event::new("autorujira.autoclaimer")
-            .add_attribute("action", "claim")
-            .add_attribute("msg_id", msg_id_str)
-            .add_attribute("result", claim_result.as_str())
-            .add_attributes(attributes);
+    let event = add_result(
+        action_event(EVENT_NAMESPACE, "claim_and_stake")
+            .add_attribute("msg_id", msg.id.to_string()),
+        claim_result,
+    )
+    .add_attributes(attributes);
 
-        // Return the final response with submessages and event
-        Ok(Response::new()
-            .add_submessages(submessages)
-            .add_event(event))
+    Ok(Response::new()
+        .add_submessages(submessages)
+        .add_event(event))
+}
+
+/// Counts a processed claim in the lifetime stats.
+fn record_claim_stats(storage: &mut dyn Storage, succeeded: bool) -> StdResult<()> {
+    let mut stats = STATS.may_load(storage)?.unwrap_or_default();
+    if succeeded {
+        stats.claims_succeeded += 1;
     } else {
-        Err(ContractError::InvalidReplyId { id: msg.id })
+        stats.claims_failed += 1;
+    }
+    STATS.save(storage, &stats)
+}
+
+/// Records the time of a failed claim so keepers can back off, if failure tracking is enabled.
+///
+/// # Arguments
+/// * `storage` - Contract storage.
+/// * `env` - Information about the environment where the contract is running.
+/// * `user` - The user whose claim failed.
+/// * `protocol` - The protocol of the failed claim.
+///
+/// # Returns
+/// A `StdResult<()>` indicating success or failure.
+fn record_claim_failure(
+    storage: &mut dyn Storage,
+    env: &Env,
+    user: &Addr,
+    protocol: &str,
+) -> StdResult<()> {
+    record_claim_stats(storage, false)?;
+
+    if !CONFIG.load(storage)?.track_failures {
+        return Ok(());
     }
+
+    let key = (user.clone(), protocol.to_string());
+    let mut execution_data = USER_EXECUTION_DATA
+        .may_load(storage, key.clone())?
+        .unwrap_or_default();
+    execution_data.last_failed_at = Some(env.block.time);
+    USER_EXECUTION_DATA.save(storage, key, &execution_data)
 }
 
 /// Processes the reply for a stake message.
@@ -545,19 +1943,10 @@ fn process_claim_and_stake_claim_reply(
 /// # Returns
 /// A `Result<Response, ContractError>` indicating success or failure.
 fn process_claim_and_stake_stake_reply(msg: Reply) -> Result<Response, ContractError> {
-    let mut event = Event::new("autorujira.autoclaimer")
-        .add_attribute("action", "stake")
-        .add_attribute("msg_id", msg.id.to_string());
-
-    match msg.result {
-        cosmwasm_std::SubMsgResult::Ok(_) => {
-            event = event.add_attribute("result", ActionResult::Ok.as_str());
-        }
-        cosmwasm_std::SubMsgResult::Err(err) => {
-            event = event.add_attribute("result", ActionResult::Failed.as_str());
-            event = event.add_attribute("error", err.as_str());
-        }
-    }
+    let event = add_submsg_result(
+        action_event(EVENT_NAMESPACE, "stake").add_attribute("msg_id", msg.id.to_string()),
+        &msg.result,
+    );
 
     Ok(Response::new().add_event(event))
 }
@@ -565,30 +1954,99 @@ fn process_claim_and_stake_stake_reply(msg: Reply) -> Result<Response, ContractE
 /// Processes the reply for a send fee message.
 ///
 /// Emits an event indicating whether the send was successful or failed.
+/// A failed send is recorded in `FAILED_FEES` so it can be retried with `RetryFees`.
 ///
 /// # Arguments
+/// * `deps` - Mutable dependencies for contract state access.
+/// * `env` - Information about the environment where the contract is running.
 /// * `msg` - The reply message after send execution.
 ///
 /// # Returns
 /// A `Result<Response, ContractError>` indicating success or failure.
-fn process_claim_and_stake_send_reply(msg: Reply) -> Result<Response, ContractError> {
-    let mut event = Event::new("autorujira.autoclaimer")
-        .add_attribute("action", "charge_fee")
-        .add_attribute("msg_id", msg.id.to_string());
+fn process_claim_and_stake_send_reply(
+    deps: DepsMut,
+    env: Env,
+    msg: Reply,
+) -> Result<Response, ContractError> {
+    let mut event = add_submsg_result(
+        action_event(EVENT_NAMESPACE, "charge_fee").add_attribute("msg_id", msg.id.to_string()),
+        &msg.result,
+    );
+
+    if let Some((user, protocol, fee_amount)) = PENDING_FEE_DATA
+        .may_load(deps.storage, msg.id)?
+        .map(|pending| pending.data)
+    {
+        PENDING_FEE_DATA.remove(deps.storage, msg.id);
 
-    match msg.result {
-        cosmwasm_std::SubMsgResult::Ok(_) => {
-            event = event.add_attribute("result", ActionResult::Ok.as_str());
+        if msg.result.is_err() {
+            FAILED_FEES.update(
+                deps.storage,
+                (user, protocol),
+                |failed| -> StdResult<Uint128> {
+                    Ok(failed.unwrap_or_default().checked_add(fee_amount)?)
+                },
+            )?;
+            event = event.add_attribute("failed_fee", fee_amount.to_string());
         }
-        cosmwasm_std::SubMsgResult::Err(err) => {
-            event = event.add_attribute("result", ActionResult::Failed.as_str());
-            event = event.add_attribute("error", err.as_str());
+    }
+
+    // Fees routed to the contract itself are tracked apart from the user funds it may hold
+    if let cosmwasm_std::SubMsgResult::Ok(response) = &msg.result {
+        for coin in coins_received(&response.events, env.contract.address.as_str())? {
+            ACCRUED_FEES.update(deps.storage, &coin.denom, |accrued| -> StdResult<Uint128> {
+                Ok(accrued.unwrap_or_default().checked_add(coin.amount)?)
+            })?;
         }
     }
 
     Ok(Response::new().add_event(event))
 }
 
+/// Processes the reply for a swap message.
+///
+/// Emits an event indicating whether the swap was successful or failed.
+///
+/// # Arguments
+/// * `msg` - The reply message after swap execution.
+///
+/// # Returns
+/// A `Result<Response, ContractError>` indicating success or failure.
+fn process_claim_and_swap_swap_reply(msg: Reply) -> Result<Response, ContractError> {
+    let event = add_submsg_result(
+        action_event(EVENT_NAMESPACE, "swap").add_attribute("msg_id", msg.id.to_string()),
+        &msg.result,
+    );
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Processes the reply for a fee stake message.
+///
+/// Emits an event indicating whether staking the fee for the treasury was successful or failed.
+///
+/// # Arguments
+/// * `msg` - The reply message after the fee stake execution.
+///
+/// # Returns
+/// A `Result<Response, ContractError>` indicating success or failure.
+fn process_claim_and_stake_stake_fee_reply(msg: Reply) -> Result<Response, ContractError> {
+    let event = add_submsg_result(
+        action_event(EVENT_NAMESPACE, "stake_fee").add_attribute("msg_id", msg.id.to_string()),
+        &msg.result,
+    );
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Queries the config of a FIN market, listing the denoms it trades.
+fn query_fin_config(deps: Deps, fin_market: &str) -> StdResult<FinConfigData> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: fin_market.to_string(),
+        msg: to_json_binary(&serde_json::json!({ "config": {} }))?,
+    }))
+}
+
 /// Executes claim-only actions for specified users and contracts.
 ///
 /// # Arguments
@@ -619,6 +2077,7 @@ pub fn execute_claim_only(
         } => {
             let mut messages: Vec<SubMsg> = vec![];
             let mut ignored_markets: Vec<(String, String)> = vec![];
+            let mut snapshot_failed_markets: Vec<(String, String)> = vec![];
 
             for (user_string, contract_address) in users_contracts {
                 if !supported_markets.contains(&contract_address) {
@@ -627,19 +2086,63 @@ pub fn execute_claim_only(
                 }
 
                 let user = deps.api.addr_validate(&user_string)?;
+
+                // Skip users not subscribed to the protocol, unless the check is disabled
+                if config.require_subscription_for_claim_only
+                    && !SUBSCRIPTIONS
+                        .may_load(deps.storage, &user)?
+                        .unwrap_or_default()
+                        .contains(&protocol)
+                {
+                    ignored_markets.push((user_string.clone(), contract_address.clone()));
+                    continue;
+                }
+
                 let contract_addr = deps.api.addr_validate(&contract_address)?;
 
+                // Snapshot the user balances of both market denoms to report what the claim paid,
+                // a market whose config or balances can not be queried is skipped
+                let balances_before = query_fin_config(deps.as_ref(), contract_addr.as_str())
+                    .and_then(|market_config| {
+                        market_config
+                            .denoms
+                            .into_iter()
+                            .map(|denom| {
+                                let amount =
+                                    query_token_balance(deps.as_ref(), &user, denom.clone())?;
+                                Ok(Coin { denom, amount })
+                            })
+                            .collect::<StdResult<Vec<Coin>>>()
+                    });
+                let balances_before = match balances_before {
+                    Ok(balances_before) => balances_before,
+                    Err(_) => {
+                        snapshot_failed_markets
+                            .push((user_string.clone(), contract_address.clone()));
+                        ignored_markets.push((user_string.clone(), contract_address.clone()));
+                        continue;
+                    }
+                };
+
                 // Build the claim message
                 let claim_msg =
                     build_FIN_claim_msg(env.clone(), user.clone(), contract_addr.clone())?;
 
                 // Create SubMsg with unique ID
-                let msg_id = CLAIM_ONLY_CLAIM_BASE_ID + messages.len() as u64;
+                let msg_id = reply_id(CLAIM_ONLY_CLAIM_BASE_ID, messages.len())?;
 
                 PENDING_CLAIM_ONLY_DATA.save(
                     deps.storage,
                     msg_id,
-                    &(protocol.clone(), user.clone(), contract_addr.clone()),
+                    &PendingData {
+                        data: (
+                            protocol.clone(),
+                            user.clone(),
+                            contract_addr.clone(),
+                            balances_before,
+                        ),
+                        saved_at: env.block.time,
+                    },
                 )?;
 
                 let submsg = SubMsg {
@@ -652,10 +2155,17 @@ pub fn execute_claim_only(
                 messages.push(submsg);
             }
 
-            let event = Event::new("autorujira.autoclaimer")
-                .add_attribute("action", "execute_claim_only")
-                .add_attribute("ignored_count", ignored_markets.len().to_string())
-                .add_attribute("ignored_markets", format!("{:?}", ignored_markets));
+            let event = add_debug_attribute(
+                add_debug_attribute(
+                    action_event(EVENT_NAMESPACE, "execute_claim_only")
+                        .add_attribute("grantee", env.contract.address.to_string())
+                        .add_attribute("ignored_count", ignored_markets.len().to_string()),
+                    "ignored_markets",
+                    &ignored_markets,
+                ),
+                "snapshot_failed",
+                &snapshot_failed_markets,
+            );
 
             Ok(Response::new().add_submessages(messages).add_event(event))
         }
@@ -681,9 +2191,13 @@ fn process_claim_only_claim_reply(
     env: Env,
     msg: Reply,
 ) -> Result<Response, ContractError> {
-    if let Some((protocol, user, contract_address)) =
-        PENDING_CLAIM_ONLY_DATA.may_load(deps.storage, msg.id)?
+    if let Some((protocol, user, contract_address, balances_before)) = PENDING_CLAIM_ONLY_DATA
+        .may_load(deps.storage, msg.id)?
+        .map(|pending| pending.data)
     {
+        // The reply consumes its pending data
+        PENDING_CLAIM_ONLY_DATA.remove(deps.storage, msg.id);
+
         let msg_id_str = msg.id.to_string();
         let mut attributes = vec![
             ("protocol".to_string(), protocol.clone()),
@@ -695,6 +2209,17 @@ fn process_claim_only_claim_reply(
 
         match msg.result {
             cosmwasm_std::SubMsgResult::Ok(_) => {
+                // One token and tokens_claimed pair per market denom the claim paid out
+                for balance_before in balances_before {
+                    let balance_after =
+                        query_token_balance(deps.as_ref(), &user, balance_before.denom.clone())?;
+                    let tokens_claimed = balance_after.saturating_sub(balance_before.amount);
+                    if !tokens_claimed.is_zero() {
+                        attributes.push(("token".to_string(), balance_before.denom));
+                        attributes.push(("tokens_claimed".to_string(), tokens_claimed.to_string()));
+                    }
+                }
+
                 // Add the timestamp as an additional attribute
                 attributes.push((
                     "timestamp".to_string(),
@@ -703,7 +2228,8 @@ fn process_claim_only_claim_reply(
 
                 // Save last autoclaim
                 let execution_data = ExecutionData {
-                    last_autoclaim: env.block.time,
+                    last_autoclaim: Some(env.block.time),
+                    last_failed_at: None,
                 };
 
                 USER_EXECUTION_DATA.save(
@@ -711,19 +2237,21 @@ fn process_claim_only_claim_reply(
                     (user.clone(), protocol.clone()),
                     &execution_data,
                 )?;
+                record_claim_stats(deps.storage, true)?;
             }
             cosmwasm_std::SubMsgResult::Err(err) => {
                 attributes.push(("error".to_string(), err.clone()));
                 claim_result = ActionResult::Failed;
+                record_claim_failure(deps.storage, &env, &user, &protocol)?;
             }
         }
 
         // Create the main event
-        let event = Event::new("autorujira.autoclaimer")
-            .add_attribute("action", "claim")
-            .add_attribute("msg_id", msg_id_str)
-            .add_attribute("result", claim_result.as_str())
-            .add_attributes(attributes);
+        let event = add_result(
+            action_event(EVENT_NAMESPACE, "claim").add_attribute("msg_id", msg_id_str),
+            claim_result,
+        )
+        .add_attributes(attributes);
 
         Ok(Response::new().add_event(event))
     } else {
@@ -755,16 +2283,29 @@ pub fn subscribe(
         }
     }
 
+    // Reject the subscription if it would exceed the per-user limit
+    let config = CONFIG.load(deps.storage)?;
+    if config.max_subscriptions_per_user > 0
+        && user_subscriptions.len() > config.max_subscriptions_per_user as usize
+    {
+        return Err(ContractError::TooManySubscriptions {
+            max_allowed: config.max_subscriptions_per_user,
+        });
+    }
+
     SUBSCRIPTIONS.save(deps.storage, &user, &user_subscriptions)?;
 
-    Ok(Response::new()
-        .add_attribute("action", "subscribe")
+    let event = action_event(EVENT_NAMESPACE, "subscribe")
         .add_attribute("user", user.to_string())
-        .add_attribute("subscribed_protocols", format!("{:?}", user_subscriptions)))
+        .add_attribute("subscribed_protocols", format!("{:?}", user_subscriptions));
+
+    Ok(Response::new().add_event(add_result(event, ActionResult::Ok)))
 }
 
 /// Unsubscribes a user from the specified protocols.
 ///
+/// Protocols the user is not subscribed to are silently skipped.
+///
 /// # Arguments
 /// * `deps` - Mutable dependencies for contract state access.
 /// * `user` - The address of the user unsubscribing.
@@ -777,31 +2318,397 @@ pub fn unsubscribe(
     user: Addr,
     protocols: Vec<String>,
 ) -> Result<Response, ContractError> {
-    let mut user_subscriptions = SUBSCRIPTIONS.load(deps.storage, &user)?;
+    if let Some(mut user_subscriptions) = SUBSCRIPTIONS.may_load(deps.storage, &user)? {
+        user_subscriptions.retain(|p| !protocols.contains(p));
+        if user_subscriptions.is_empty() {
+            SUBSCRIPTIONS.remove(deps.storage, &user);
+        } else {
+            SUBSCRIPTIONS.save(deps.storage, &user, &user_subscriptions)?;
+        }
+    }
+
+    // Give the user a clean slate if configured, the claim ids are kept as claims depend on them
+    if CONFIG.load(deps.storage)?.purge_history_on_unsubscribe {
+        for protocol in &protocols {
+            USER_EXECUTION_DATA.remove(deps.storage, (user.clone(), protocol.clone()));
+        }
+    }
+
+    let event =
+        action_event(EVENT_NAMESPACE, "unsubscribe").add_attribute("user", user.to_string());
+
+    Ok(Response::new().add_event(add_result(event, ActionResult::Ok)))
+}
+
+/// Removes every subscription and the execution history of a user in one step.
+///
+/// Claim ids and failed fees are kept: the claim contracts keep counting claim ids for the
+/// user, and failed fees are still owed if the user subscribes again.
+///
+/// # Arguments
+/// * `deps` - Mutable dependencies for contract state access.
+/// * `user` - The address of the user to offboard.
+///
+/// # Returns
+/// A `Result<Response, ContractError>` indicating success or failure.
+pub fn offboard_user(deps: DepsMut, user: Addr) -> Result<Response, ContractError> {
+    SUBSCRIPTIONS.remove(deps.storage, &user);
 
+    let protocols: Vec<String> = USER_EXECUTION_DATA
+        .prefix(user.clone())
+        .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<String>>>()?;
     for protocol in protocols {
-        if let Some(index) = user_subscriptions.iter().position(|p| p == &protocol) {
-            user_subscriptions.remove(index);
+        USER_EXECUTION_DATA.remove(deps.storage, (user.clone(), protocol));
+    }
+
+    let event = action_event(EVENT_NAMESPACE, "offboard").add_attribute("user", user.to_string());
+
+    Ok(Response::new().add_event(add_result(event, ActionResult::Ok)))
+}
+
+/// Removes deleted protocols from the subscriptions, and the entries left with no protocols.
+///
+/// At most `limit` entries are visited per call, starting after `start_after`, so large maps
+/// can be cleaned in chunks. While entries are left to visit, the last visited address is
+/// returned in the `next_start_after` attribute to resume from.
+///
+/// # Arguments
+/// * `deps` - Mutable dependencies for contract state access.
+/// * `start_after` - The address after which the scan starts.
+/// * `limit` - The maximum number of entries to visit.
+///
+/// # Returns
+/// A `Result<Response, ContractError>` indicating success or failure.
+pub fn prune_empty_subscriptions(
+    deps: DepsMut,
+    start_after: Option<Addr>,
+    limit: u32,
+) -> Result<Response, ContractError> {
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let visited: Vec<(Addr, Vec<String>)> = SUBSCRIPTIONS
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit as usize)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut pruned_count = 0;
+    let mut updated_count = 0;
+    for (user, protocols) in &visited {
+        let live_protocols: Vec<String> = protocols
+            .iter()
+            .filter(|protocol| PROTOCOL_CONFIG.has(deps.storage, protocol))
+            .cloned()
+            .collect();
+        if live_protocols.is_empty() {
+            SUBSCRIPTIONS.remove(deps.storage, user);
+            pruned_count += 1;
+        } else if live_protocols.len() < protocols.len() {
+            SUBSCRIPTIONS.save(deps.storage, user, &live_protocols)?;
+            updated_count += 1;
         }
     }
 
-    SUBSCRIPTIONS.save(deps.storage, &user, &user_subscriptions)?;
+    let mut event = action_event(EVENT_NAMESPACE, "prune_empty_subscriptions")
+        .add_attribute("pruned_count", pruned_count.to_string())
+        .add_attribute("updated_count", updated_count.to_string());
+    if visited.len() == limit as usize {
+        if let Some((last_user, _)) = visited.last() {
+            event = event.add_attribute("next_start_after", last_user.to_string());
+        }
+    }
+
+    Ok(Response::new().add_event(add_result(event, ActionResult::Ok)))
+}
+
+/// Retries the fee sends that failed for the given users and protocols.
+///
+/// The recorded amount is cleared before sending; if the send fails again the reply records it back.
+/// Pairs without failed fees are skipped.
+///
+/// # Arguments
+/// * `deps` - Mutable dependencies for contract state access.
+/// * `env` - Information about the environment where the contract is running.
+/// * `users_protocols` - The (user, protocol) pairs whose failed fees are retried.
+///
+/// # Returns
+/// A `Result<Response, ContractError>` indicating success or failure.
+pub fn retry_fees(
+    deps: DepsMut,
+    env: Env,
+    users_protocols: Vec<(Addr, String)>,
+) -> Result<Response, ContractError> {
+    let mut messages: Vec<SubMsg> = vec![];
+
+    for (user, protocol) in users_protocols {
+        let Some(fee_amount) =
+            FAILED_FEES.may_load(deps.storage, (user.clone(), protocol.clone()))?
+        else {
+            continue;
+        };
+
+        let protocol_config = PROTOCOL_CONFIG.may_load(deps.storage, &protocol)?.ok_or(
+            ContractError::InvalidProtocol {
+                protocol: protocol.clone(),
+            },
+        )?;
+        // Kept for later if the protocol no longer charges fees
+        if !charges_fee(&protocol_config) {
+            continue;
+        }
+        let reward_denom = resolve_reward_denom(deps.as_ref(), &protocol_config)?;
+        ensure_denom_sendable(deps.storage, reward_denom.as_str())?;
+        let effective_fee = resolve_effective_fee(&protocol_config, Some(reward_denom.as_str()));
+
+        let send_msg = build_send_msg(
+            env.clone(),
+            user.clone(),
+            deps.api.addr_validate(&effective_fee.destination)?,
+            fee_amount.u128(),
+            reward_denom,
+        )?;
+
+        let msg_id = reply_id(CLAIM_AND_STAKE_SEND_BASE_ID, messages.len())?;
+        FAILED_FEES.remove(deps.storage, (user.clone(), protocol.clone()));
+        PENDING_FEE_DATA.save(
+            deps.storage,
+            msg_id,
+            &PendingData {
+                data: (user, protocol, fee_amount),
+                saved_at: env.block.time,
+            },
+        )?;
+
+        messages.push(SubMsg {
+            msg: send_msg,
+            gas_limit: None,
+            id: msg_id,
+            reply_on: ReplyOn::Always,
+        });
+    }
+
+    let event = action_event(EVENT_NAMESPACE, "retry_fees")
+        .add_attribute("retried_count", messages.len().to_string());
+
+    Ok(Response::new().add_submessages(messages).add_event(event))
+}
+
+/// Removes pending reply data saved before `older_than`.
+///
+/// Replies normally consume their pending data, so anything old enough is an orphan.
+/// Entries saved before timestamps were recorded cannot be dated and are removed as well.
+///
+/// # Arguments
+/// * `deps` - Mutable dependencies for contract state access.
+/// * `older_than` - Entries saved before this time are removed.
+/// * `limit` - The maximum number of entries to remove.
+///
+/// # Returns
+/// A `Result<Response, ContractError>` indicating success or failure.
+pub fn gc_pending(
+    deps: DepsMut,
+    older_than: Timestamp,
+    limit: u32,
+) -> Result<Response, ContractError> {
+    let limit = limit as usize;
+    let mut removed = 0;
+
+    removed += gc_pending_map(
+        deps.storage,
+        &PENDING_CLAIM_AND_STAKE_DATA,
+        older_than,
+        limit - removed,
+    )?;
+    removed += gc_pending_map(
+        deps.storage,
+        &PENDING_CLAIM_ONLY_DATA,
+        older_than,
+        limit - removed,
+    )?;
+    removed += gc_pending_map(deps.storage, &PENDING_FEE_DATA, older_than, limit - removed)?;
+    removed += gc_pending_map(
+        deps.storage,
+        &PENDING_ATOMIC_CLAIM_AND_STAKE_DATA,
+        older_than,
+        limit - removed,
+    )?;
+
+    let event = action_event(EVENT_NAMESPACE, "gc_pending")
+        .add_attribute("older_than", older_than.seconds().to_string())
+        .add_attribute("removed_count", removed.to_string());
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Removes up to `limit` entries of a pending map saved before `older_than` or that cannot be decoded.
+///
+/// # Returns
+/// A `StdResult<usize>` with the number of removed entries.
+fn gc_pending_map<T>(
+    storage: &mut dyn Storage,
+    map: &Map<u64, PendingData<T>>,
+    older_than: Timestamp,
+    limit: usize,
+) -> StdResult<usize>
+where
+    T: Serialize + DeserializeOwned,
+{
+    // Raw keys, as decoding the keys would also decode the legacy values
+    let ids = map
+        .keys_raw(storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(u64::from_vec)
+        .collect::<StdResult<Vec<u64>>>()?;
+
+    let mut removed = 0;
+    for id in ids {
+        if removed >= limit {
+            break;
+        }
+
+        let expired = match map.may_load(storage, id) {
+            Ok(pending) => pending.is_some_and(|pending| pending.saved_at < older_than),
+            Err(_) => true,
+        };
+        if expired {
+            map.remove(storage, id);
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Sends fees accrued by the contract to the given address.
+///
+/// Only the fees the contract received from fee sends are withdrawable. Its balance can also
+/// hold user funds, e.g. the rewards of `direct_claim` protocols, which are never withdrawn.
+///
+/// # Arguments
+/// * `deps` - Mutable dependencies for contract state access.
+/// * `denom` - The denomination of the fees to withdraw.
+/// * `amount` - The amount to withdraw, or all accrued fees if omitted.
+/// * `to_address` - The recipient of the fees.
+///
+/// # Returns
+/// A `Result<Response, ContractError>` indicating success or failure.
+pub fn withdraw_fees(
+    deps: DepsMut,
+    denom: String,
+    amount: Option<Uint128>,
+    to_address: String,
+) -> Result<Response, ContractError> {
+    ensure_denom_sendable(deps.storage, &denom)?;
+    let recipient = deps.api.addr_validate(&to_address)?;
+    let accrued = ACCRUED_FEES
+        .may_load(deps.storage, &denom)?
+        .unwrap_or_default();
+    let amount = amount.unwrap_or(accrued);
+
+    if amount.is_zero() || amount > accrued {
+        return Err(ContractError::NoFeesToWithdraw { denom });
+    }
+    ACCRUED_FEES.save(deps.storage, &denom, &(accrued - amount))?;
+
+    let event = action_event(EVENT_NAMESPACE, "withdraw_fees")
+        .add_attribute("to_address", recipient.to_string())
+        .add_attribute("denom", denom.clone())
+        .add_attribute("amount", amount.to_string());
 
     Ok(Response::new()
-        .add_attribute("action", "unsubscribe")
-        .add_attribute("user", user.to_string()))
+        .add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin { denom, amount }],
+        })
+        .add_event(add_result(event, ActionResult::Ok)))
+}
+
+/// Queries the rewards a user can claim on a protocol without claiming them.
+///
+/// Sends the protocol `pending_rewards_query` to its claim contract, which must answer
+/// with a `PendingRewardsData`.
+///
+/// # Arguments
+/// * `deps` - Dependencies for contract state access.
+/// * `user` - The address of the user.
+/// * `protocol` - The protocol to preview.
+///
+/// # Returns
+/// A `StdResult<PreviewRewardsResponse>` with the claimable amount and its denom.
+pub fn query_preview_rewards(
+    deps: Deps,
+    user: Addr,
+    protocol: String,
+) -> StdResult<PreviewRewardsResponse> {
+    let protocol_config = PROTOCOL_CONFIG.load(deps.storage, &protocol)?;
+
+    let claim_contract_address = match &protocol_config.strategy {
+        ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+            claim_contract_address,
+            ..
+        }
+        | ProtocolStrategy::ClaimAndSwapFIN {
+            claim_contract_address,
+            ..
+        }
+        | ProtocolStrategy::ClaimAndSend {
+            claim_contract_address,
+            ..
+        } => claim_contract_address,
+        ProtocolStrategy::ClaimOnlyFIN { .. } => {
+            return Err(StdError::generic_err(format!(
+                "Rewards preview is not supported for protocol {}",
+                protocol
+            )))
+        }
+    };
+
+    let query = protocol_config
+        .pending_rewards_query
+        .as_ref()
+        .ok_or_else(|| {
+            StdError::generic_err(format!(
+                "No pending rewards query configured for protocol {}",
+                protocol
+            ))
+        })?
+        .replace("{user}", user.as_str());
+
+    let pending: PendingRewardsData =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: claim_contract_address.clone(),
+            msg: Binary::from(query.as_bytes()),
+        }))?;
+
+    let denom = resolve_reward_denom(deps, &protocol_config)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    Ok(PreviewRewardsResponse {
+        amount: pending.amount,
+        denom,
+    })
 }
 
-/// Queries all user subscriptions stored in the contract.
+/// Queries a page of the user subscriptions stored in the contract, ordered by address.
 ///
 /// # Arguments
 /// * `deps` - Dependencies for contract state access.
+/// * `start_after` - The address after which the page starts, `None` for the first page.
+/// * `limit` - The page size, capped at `MAX_SUBSCRIPTIONS_LIMIT`.
 ///
 /// # Returns
 /// A `StdResult<GetSubscriptionsResponse>` containing the list of subscriptions.
-pub fn query_get_subscriptions(deps: Deps) -> StdResult<GetSubscriptionsResponse> {
+pub fn query_get_subscriptions(
+    deps: Deps,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<GetSubscriptionsResponse> {
+    let limit = limit
+        .unwrap_or(DEFAULT_SUBSCRIPTIONS_LIMIT)
+        .min(MAX_SUBSCRIPTIONS_LIMIT) as usize;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
     let subscriptions: Vec<_> = SUBSCRIPTIONS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
         .map(|item| {
             let (addr, protocols) = item?;
             Ok((addr.to_string(), protocols))
@@ -833,11 +2740,19 @@ pub fn query_get_subscribed_protocols(
         let execution_data =
             USER_EXECUTION_DATA.may_load(deps.storage, (user.clone(), protocol.clone()))?;
 
-        let last_autoclaim = execution_data.map(|data| data.last_autoclaim.seconds());
+        let last_autoclaim = execution_data
+            .as_ref()
+            .and_then(|data| data.last_autoclaim)
+            .map(|time| time.seconds());
+        let last_failed_at = execution_data
+            .as_ref()
+            .and_then(|data| data.last_failed_at)
+            .map(|time| time.seconds());
 
         protocols_data.push(ProtocolSubscriptionData {
             protocol,
             last_autoclaim,
+            last_failed_at,
         });
     }
 
@@ -850,12 +2765,19 @@ pub fn query_get_subscribed_protocols(
 ///
 /// Supported queries include:
 /// - `Config`: Retrieves the protocol configuration.
-/// - `GetSubscriptions`: Retrieves all user subscriptions.
+/// - `GetProtocolConfig`: Retrieves the configuration of a single protocol.
+/// - `GetSubscriptions`: Retrieves a page of user subscriptions.
 /// - `GetSubscribedProtocols`: Retrieves a specific user's subscriptions.
+/// - `IsPaused`: Retrieves whether the contract is paused.
+/// - `IsSubscribed`: Retrieves whether a user is subscribed to a protocol.
+/// - `Overview`: Retrieves the configuration, lifetime stats, and pause flag together.
+/// - `ValidateProtocolConfig`: Validates a protocol configuration without saving it.
+/// - `ProtocolCount`: Retrieves the number of configured protocols.
+/// - `QuoteBatchFees`: Retrieves the fees charged on a hypothetical batch of claims.
 ///
 /// # Arguments
 /// * `deps` - Dependencies for contract state access.
-/// * `_env` - Information about the environment where the contract is running.
+/// * `env` - Information about the environment where the contract is running.
 /// * `msg` - The query message specifying the data to retrieve.
 ///
 /// # Returns
@@ -864,12 +2786,129 @@ pub fn query_get_subscribed_protocols(
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
-        QueryMsg::GetSubscriptions {} => to_json_binary(&query_get_subscriptions(deps)?),
+        QueryMsg::GetProtocolConfig { protocol } => to_json_binary(&ProtocolConfigResponse {
+            config: PROTOCOL_CONFIG
+                .may_load(deps.storage, &protocol)?
+                .ok_or_else(|| StdError::not_found(format!("protocol config of {protocol}")))?,
+        }),
+        QueryMsg::GetSubscriptions { start_after, limit } => {
+            let start_after = start_after
+                .map(|address| validate_query_address(deps.api, "start_after", &address))
+                .transpose()?;
+            to_json_binary(&query_get_subscriptions(deps, start_after, limit)?)
+        }
         QueryMsg::GetSubscribedProtocols { user_address } => {
-            let user_addr = deps.api.addr_validate(&user_address)?;
+            let user_addr = validate_query_address(deps.api, "user_address", &user_address)?;
             to_json_binary(&query_get_subscribed_protocols(deps, user_addr)?)
         }
+        QueryMsg::IsPaused {} => to_json_binary(&IsPausedResponse {
+            paused: CONFIG.load(deps.storage)?.paused,
+        }),
+        QueryMsg::Overview {} => to_json_binary(&OverviewResponse {
+            config: query_config(deps)?,
+            stats: STATS.may_load(deps.storage)?.unwrap_or_default(),
+            paused: CONFIG.load(deps.storage)?.paused,
+        }),
+        QueryMsg::IsSubscribed {
+            user_address,
+            protocol,
+        } => {
+            let user_addr = validate_query_address(deps.api, "user_address", &user_address)?;
+            to_json_binary(&IsSubscribedResponse {
+                subscribed: SUBSCRIPTIONS
+                    .may_load(deps.storage, &user_addr)?
+                    .unwrap_or_default()
+                    .contains(&protocol),
+            })
+        }
+        QueryMsg::NextClaimId {
+            user_address,
+            protocol,
+        } => {
+            let user_addr = validate_query_address(deps.api, "user_address", &user_address)?;
+            let protocol_config = PROTOCOL_CONFIG.load(deps.storage, &protocol)?;
+            to_json_binary(&NextClaimIdResponse {
+                claim_id: load_next_claim_id(deps.storage, &user_addr, &protocol_config)?,
+            })
+        }
+        QueryMsg::PreviewRewards {
+            user_address,
+            protocol,
+        } => {
+            let user_addr = validate_query_address(deps.api, "user_address", &user_address)?;
+            to_json_binary(&query_preview_rewards(deps, user_addr, protocol)?)
+        }
+        QueryMsg::EffectiveFee { protocol, .. } => {
+            let protocol_config = PROTOCOL_CONFIG.load(deps.storage, &protocol)?;
+            let denom = fee_denom(deps, &protocol_config)?;
+            to_json_binary(&resolve_effective_fee(&protocol_config, denom.as_deref()))
+        }
+        QueryMsg::QuoteBatchFees { items } => to_json_binary(&query_quote_batch_fees(deps, items)?),
+        QueryMsg::DenomDrift { protocol } => to_json_binary(&DenomDriftResponse {
+            observed_denom: DENOM_DRIFT.may_load(deps.storage, &protocol)?,
+        }),
+        QueryMsg::ProtocolCount {} => to_json_binary(&ProtocolCountResponse {
+            count: PROTOCOL_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+        }),
+        QueryMsg::ValidateProtocolConfig { config } => {
+            let errors = protocol_config_errors(deps, &config);
+            to_json_binary(&ValidateProtocolConfigResponse {
+                valid: errors.is_empty(),
+                errors,
+            })
+        }
+    }
+}
+
+/// Returns the denom fees of a protocol are charged in, `None` for claim only protocols.
+fn fee_denom(deps: Deps, protocol_config: &ProtocolConfig) -> StdResult<Option<String>> {
+    match protocol_config.strategy {
+        ProtocolStrategy::ClaimOnlyFIN { .. } => Ok(None),
+        _ => resolve_reward_denom(deps, protocol_config)
+            .map(Some)
+            .map_err(|err| StdError::generic_err(err.to_string())),
+    }
+}
+
+/// Quotes the fees charged on a batch of claimed amounts, using the same fee rules as claims.
+///
+/// Fees do not depend on the user, so the quote holds for any user of the batch.
+///
+/// # Arguments
+/// * `deps` - Dependencies for contract state access.
+/// * `items` - The (protocol, amount claimed) pairs of the batch.
+///
+/// # Returns
+/// A `StdResult<QuoteBatchFeesResponse>` with the fee per protocol and the total.
+fn query_quote_batch_fees(
+    deps: Deps,
+    items: Vec<(String, Uint128)>,
+) -> StdResult<QuoteBatchFeesResponse> {
+    let mut fees: Vec<(String, Uint128)> = vec![];
+    for (protocol, amount) in items {
+        let protocol_config = PROTOCOL_CONFIG.load(deps.storage, &protocol)?;
+        let denom = fee_denom(deps, &protocol_config)?;
+        let effective_fee = resolve_effective_fee(&protocol_config, denom.as_deref());
+        let fee = compute_fee(&effective_fee, amount);
+
+        // The batch is caller supplied, so its totals can overflow
+        match fees.iter_mut().find(|(quoted, _)| *quoted == protocol) {
+            Some((_, total)) => *total = total.checked_add(fee)?,
+            None => fees.push((protocol, fee)),
+        }
     }
+
+    let total = fees
+        .iter()
+        .try_fold(Uint128::zero(), |total, (_, fee)| total.checked_add(*fee))?;
+    Ok(QuoteBatchFeesResponse { fees, total })
+}
+
+/// Validates an address given to a query, naming the offending field in the error
+/// instead of surfacing the raw api error alone.
+fn validate_query_address(api: &dyn Api, field: &str, address: &str) -> StdResult<Addr> {
+    api.addr_validate(address)
+        .map_err(|err| StdError::generic_err(format!("Invalid {field} {address:?}: {err}")))
 }
 
 /// Queries the configuration of the protocol stored in the contract.
@@ -889,6 +2928,15 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     Ok(ConfigResponse {
         owner: config.owner,
         max_parallel_claims: config.max_parallel_claims,
+        min_claim_interval_seconds: config.min_claim_interval_seconds,
+        max_subscriptions_per_user: config.max_subscriptions_per_user,
+        max_protocols_per_user_per_call: config.max_protocols_per_user_per_call,
+        fee_first: config.fee_first,
+        track_failures: config.track_failures,
+        send_denylist: config.send_denylist,
+        require_subscription_for_claim_only: config.require_subscription_for_claim_only,
+        purge_history_on_unsubscribe: config.purge_history_on_unsubscribe,
+        pending_owner: config.pending_owner,
         protocol_configs,
     })
 }
@@ -1,5 +1,5 @@
 // src/error.rs
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Timestamp};
 use serde_json::Error as SerdeError;
 use thiserror::Error;
 
@@ -23,6 +23,9 @@ pub enum ContractError {
     #[error("Invalid reply ID: {id}")]
     InvalidReplyId { id: u64 },
 
+    #[error("Batch too large for the reply ID space, the maximum allowed is {max_allowed}")]
+    ReplyIdOverflow { max_allowed: u64 },
+
     #[error("Serialization error: {0}")]
     SerializationError(String), // Nuevo error para manejo de serialización
 
@@ -34,6 +37,30 @@ pub enum ContractError {
 
     #[error("Unsupported strategy: {strategy}")]
     InvalidStrategy { strategy: String },
+
+    #[error("Contract is paused")]
+    Paused,
+
+    #[error("Too many subscriptions, the maximum allowed is {max_allowed}")]
+    TooManySubscriptions { max_allowed: u32 },
+
+    #[error("Too many protocols for user {user}, the maximum allowed per call is {max_allowed}")]
+    TooManyProtocolsPerUser { user: String, max_allowed: u32 },
+
+    #[error("No fees to withdraw in {denom}")]
+    NoFeesToWithdraw { denom: String },
+
+    #[error("Invalid reward denom returned by {contract}")]
+    InvalidRewardDenom { contract: String },
+
+    #[error("Denom {denom} is not allowed to be sent")]
+    DeniedDenom { denom: String },
+
+    #[error("Deadline exceeded: {deadline}")]
+    DeadlineExceeded { deadline: Timestamp },
+
+    #[error("Invalid config for protocol {protocol}: {errors}")]
+    InvalidProtocolConfig { protocol: String, errors: String },
 }
 
 // From<serde_json::Error> impl for ContractError
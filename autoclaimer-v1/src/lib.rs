@@ -1,10 +1,10 @@
 pub mod contract;
 mod error;
 pub mod helpers;
+#[cfg(test)]
+pub mod mocks;
 pub mod msg;
 pub mod state;
 pub mod tests;
-#[cfg(test)]
-pub mod mocks;
 
 pub use crate::error::ContractError;
@@ -3,8 +3,12 @@
 #[cfg(test)]
 pub mod mock_functions {
     use crate::error::ContractError;
+    use common::common_functions::AuthzMessageType;
     use common::staking_provider::StakingProvider;
-    use cosmwasm_std::{to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Env, Uint128, WasmMsg};
+    use cosmwasm_std::{
+        from_json, to_json_binary, to_json_string, Addr, BankMsg, Coin, CosmosMsg, Env, StdError,
+        Uint128, WasmMsg,
+    };
     use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
 
@@ -25,6 +29,21 @@ pub mod mock_functions {
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
     pub enum MockClaimExecuteMsg {
         Claim(ClaimMsg),
+        ClaimToSender {}, // Claims the position of the caller, built by direct claims
+    }
+
+    // Define ClaimAndStakeMsg struct, a claim followed by a stake of the user in the same message
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    pub struct ClaimAndStakeMsg {
+        pub user_address: String,
+        pub stake_contract_address: String,
+        pub stake: StakeMsg,
+    }
+
+    // Define execute messages for mock atomic claim contract
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    pub enum MockAtomicClaimExecuteMsg {
+        ClaimAndStake(ClaimAndStakeMsg),
     }
 
     // Define execute messages for mock stake contract
@@ -33,9 +52,23 @@ pub mod mock_functions {
         Stake(StakeMsg),
     }
 
+    // Define SwapMsg struct
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    pub struct SwapMsg {
+        pub user_address: String,
+        pub min_return: Option<Uint128>,
+    }
+
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
     pub enum MockFINExecuteMsg {
-        WithdrawOrders(),
+        WithdrawOrders(ClaimMsg),
+        Swap(SwapMsg),
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    pub enum MockFINQueryMsg {
+        Config {},
     }
 
     pub fn build_claim_msg(
@@ -43,7 +76,7 @@ pub mod mock_functions {
         user: Addr,
         _provider: StakingProvider,
         claim_contract_addr: Addr,
-        _claim_id: u64,
+        _claim_id: Option<u64>,
     ) -> Result<CosmosMsg, ContractError> {
         let claim_msg = MockClaimExecuteMsg::Claim(ClaimMsg {
             user_address: user.to_string(),
@@ -55,6 +88,18 @@ pub mod mock_functions {
         }))
     }
 
+    pub fn build_direct_claim_msg(
+        _provider: StakingProvider,
+        claim_contract_addr: Addr,
+        _claim_id: Option<u64>,
+    ) -> Result<CosmosMsg, ContractError> {
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: claim_contract_addr.to_string(),
+            msg: to_json_binary(&MockClaimExecuteMsg::ClaimToSender {})?,
+            funds: vec![],
+        }))
+    }
+
     pub fn build_stake_msg(
         _env: Env,
         _user: Addr,
@@ -87,18 +132,87 @@ pub mod mock_functions {
         Ok(CosmosMsg::Bank(BankMsg::Send {
             to_address: to_address.to_string(),
             amount: vec![cosmwasm_std::Coin {
-                denom: denom,
+                denom,
                 amount: amount.into(),
             }],
         }))
     }
 
+    pub fn claim_msg_type(
+        _provider: StakingProvider,
+        claim_contract_addr: Addr,
+        _claim_id: Option<u64>,
+    ) -> Result<AuthzMessageType, ContractError> {
+        Ok(AuthzMessageType::ExecuteContract {
+            contract_addr: claim_contract_addr,
+            msg_str: "{}".to_string(),
+            funds: vec![],
+        })
+    }
+
+    pub fn stake_msg_type(
+        _provider: StakingProvider,
+        stake_contract_addr: Addr,
+        amount: u128,
+        denom: String,
+    ) -> Result<AuthzMessageType, ContractError> {
+        let stake_msg = StakeMsg {
+            amount: Uint128::from(amount),
+            denom,
+        };
+        Ok(AuthzMessageType::ExecuteContract {
+            contract_addr: stake_contract_addr,
+            msg_str: to_json_string(&stake_msg)?,
+            funds: vec![],
+        })
+    }
+
+    // Multi-test has no authz, so a claim and stake batch is sent to the claim contract,
+    // which pays the user and stakes on its behalf in the same execution
+    pub fn build_authz_msg_batch(
+        _env: Env,
+        user: Addr,
+        authz_msg_types: Vec<AuthzMessageType>,
+    ) -> Result<CosmosMsg, ContractError> {
+        let mut authz_msg_types = authz_msg_types.into_iter();
+        let (
+            Some(AuthzMessageType::ExecuteContract {
+                contract_addr: claim_contract_addr,
+                ..
+            }),
+            Some(AuthzMessageType::ExecuteContract {
+                contract_addr: stake_contract_addr,
+                msg_str: stake_msg_str,
+                ..
+            }),
+        ) = (authz_msg_types.next(), authz_msg_types.next())
+        else {
+            return Err(StdError::generic_err("Expected a claim and a stake message").into());
+        };
+
+        let stake_msg: StakeMsg = from_json(stake_msg_str)?;
+        let claim_and_stake_msg = MockAtomicClaimExecuteMsg::ClaimAndStake(ClaimAndStakeMsg {
+            user_address: user.to_string(),
+            stake_contract_address: stake_contract_addr.to_string(),
+            stake: stake_msg,
+        });
+
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: claim_contract_addr.to_string(),
+            msg: to_json_binary(&claim_and_stake_msg)?,
+            funds: vec![],
+        }))
+    }
+
+    #[allow(non_snake_case)]
     pub fn build_FIN_claim_msg(
         _env: Env,
-        _user: Addr,
+        user: Addr,
         contract_address: Addr,
     ) -> Result<CosmosMsg, ContractError> {
-        let claim_msg = MockFINExecuteMsg::WithdrawOrders();
+        let claim_msg = MockFINExecuteMsg::WithdrawOrders(ClaimMsg {
+            user_address: user.to_string(),
+        });
 
         Ok(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: contract_address.to_string(),
@@ -106,4 +220,28 @@ pub mod mock_functions {
             funds: vec![],
         }))
     }
+
+    #[allow(non_snake_case)]
+    pub fn build_FIN_swap_msg(
+        _env: Env,
+        user: Addr,
+        contract_address: Addr,
+        amount: u128,
+        denom: String,
+        min_return: Option<Uint128>,
+    ) -> Result<CosmosMsg, ContractError> {
+        let swap_msg = MockFINExecuteMsg::Swap(SwapMsg {
+            user_address: user.to_string(),
+            min_return,
+        });
+
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_address.to_string(),
+            msg: to_json_binary(&swap_msg)?,
+            funds: vec![Coin {
+                denom,
+                amount: Uint128::from(amount),
+            }],
+        }))
+    }
 }
@@ -1,6 +1,6 @@
 use common::staking_provider::StakingProvider;
 use cosmwasm_schema::QueryResponses;
-use cosmwasm_std::{Addr, Decimal};
+use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -20,7 +20,23 @@ pub struct ProtocolConfig {
     pub protocol: String,        // Protocol identifier (e.g., "AUTO", "MNTA", "FIN")
     pub fee_percentage: Decimal, // Fee percentage (e.g., "0.01" for 1%)
     pub fee_address: String,     // Address where the fee is sent
+    #[serde(default)]
+    pub fee_destination: FeeDestination, // How the fee is handed over
     pub strategy: ProtocolStrategy, // Specific strategy for the protocol
+    #[serde(default)]
+    pub min_claim_interval: Option<u64>, // Overrides the global min_claim_interval_seconds
+    #[serde(default)]
+    pub fee_percentages: Vec<(String, Decimal)>, // Per denom fee percentages overriding fee_percentage
+    #[serde(default)]
+    pub pending_rewards_query: Option<String>, // Query json sent to the claim contract, "{user}" is replaced by the user address
+    #[serde(default)]
+    pub min_claim_reward: Uint128, // Skip claims whose previewed rewards are below this amount (0 = disabled)
+    #[serde(default)]
+    pub direct_claim: bool, // Claim with a plain execute from the contract, for positions it holds
+    #[serde(default)]
+    pub claim_window: Option<ClaimWindow>, // Recurring window outside of which claims are skipped
+    #[serde(default)]
+    pub min_claim_amount: Option<Uint128>, // Claimed amounts below this are left in the user wallet, not staked
 }
 
 /// Enum for defining the strategy of a protocol
@@ -33,6 +49,36 @@ pub enum ProtocolStrategy {
         claim_contract_address: String, // Address of the claim contract
         stake_contract_address: String, // Address of the stake contract
         reward_denom: String,      // Denomination of the reward token (e.g., "ukuji")
+        #[serde(default)]
+        claim_id: Option<u64>, // Claim ID, required by DAO_DAO and ignored by CW_REWARDS
+        #[serde(default)]
+        reward_denom_query: Option<(Addr, String)>, // (contract, query json) returning the reward denom, overrides reward_denom
+        #[serde(default)]
+        atomic: bool, // Claim and stake in a single authz batch, requires pending_rewards_query
+    },
+    /// Strategy for claim and swap on a FIN market (e.g., rewards paid out in a stable denom)
+    ClaimAndSwapFIN {
+        provider: StakingProvider, // Associated claim provider (e.g., CW_REWARDS)
+        claim_contract_address: String, // Address of the claim contract
+        reward_denom: String,      // Denomination of the reward token
+        fin_market: String,        // Address of the FIN market used for the swap
+        target_denom: String,      // Denomination the user receives from the swap
+        #[serde(default)]
+        min_price: Option<Decimal>, // Minimum target_denom received per reward unit, the swap fails below it
+        #[serde(default)]
+        claim_id: Option<u64>, // Claim ID, required by DAO_DAO and ignored by CW_REWARDS
+        #[serde(default)]
+        reward_denom_query: Option<(Addr, String)>, // (contract, query json) returning the reward denom, overrides reward_denom
+    },
+    /// Strategy for claim and send, the claimed rewards are paid to the user wallet net of fees
+    ClaimAndSend {
+        provider: StakingProvider, // Associated claim provider (e.g., CW_REWARDS)
+        claim_contract_address: String, // Address of the claim contract
+        reward_denom: String,      // Denomination of the reward token
+        #[serde(default)]
+        claim_id: Option<u64>, // Claim ID, required by DAO_DAO and ignored by CW_REWARDS
+        #[serde(default)]
+        reward_denom_query: Option<(Addr, String)>, // (contract, query json) returning the reward denom, overrides reward_denom
     },
     /// Strategy for claim only (e.g., FIN)
     ClaimOnlyFIN {
@@ -45,24 +91,89 @@ impl ProtocolStrategy {
     pub fn as_str(&self) -> &'static str {
         match self {
             ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards { .. } => "ClaimAndStakeDaoDaoCwRewards",
+            ProtocolStrategy::ClaimAndSwapFIN { .. } => "ClaimAndSwapFIN",
+            ProtocolStrategy::ClaimAndSend { .. } => "ClaimAndSend",
             ProtocolStrategy::ClaimOnlyFIN { .. } => "ClaimOnlyFIN",
             // Agrega aquí otras estrategias según sea necesario
         }
     }
 }
+
+/// Defines what happens to the fee charged on a claim
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub enum FeeDestination {
+    /// Send the fee to the protocol fee_address
+    Send {},
+    /// Send the fee to the treasury and stake it on the protocol stake contract on its behalf.
+    /// The treasury must grant the contract the same authz permissions as users.
+    Stake { treasury: Addr },
+}
+
+impl Default for FeeDestination {
+    fn default() -> Self {
+        FeeDestination::Send {}
+    }
+}
+
+/// Recurring time window in which a protocol can be claimed.
+/// Block times are taken modulo `period`, and the window wraps around when `start > end`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimWindow {
+    pub period: u64, // Length of the cycle in seconds (e.g., 86400 for daily windows)
+    pub start: u64,  // Offset in the cycle where the window opens, inclusive
+    pub end: u64,    // Offset in the cycle where the window closes, exclusive
+}
+
+impl ClaimWindow {
+    /// Whether `time` falls inside the window
+    pub fn contains(&self, time: Timestamp) -> bool {
+        let offset = time.seconds() % self.period;
+        if self.start <= self.end {
+            self.start <= offset && offset < self.end
+        } else {
+            offset >= self.start || offset < self.end
+        }
+    }
+}
+
 /// Message used for the initial contract configuration during instantiation
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub owner: Addr,             // Owner address, mandatory at instantiation
     pub max_parallel_claims: u8, // Maximum number of parallel claims
+    #[serde(default)]
+    pub min_claim_interval_seconds: u64, // Minimum seconds between claims (0 = disabled)
+    #[serde(default)]
+    pub max_subscriptions_per_user: u32, // Maximum subscriptions per user (0 = unlimited)
+    #[serde(default)]
+    pub max_protocols_per_user_per_call: u32, // Maximum protocols per user in one call (0 = unlimited)
+    #[serde(default = "crate::state::default_fee_first")]
+    pub fee_first: bool, // Charge the fee before the stake/swap of the net amount
+    #[serde(default)]
+    pub track_failures: bool, // Record the time of failed claims
+    #[serde(default)]
+    pub send_denylist: Vec<String>, // Denoms the contract never sends
+    #[serde(default = "crate::state::default_require_subscription_for_claim_only")]
+    pub require_subscription_for_claim_only: bool, // Skip claim only pairs whose user is not subscribed
+    #[serde(default)]
+    pub purge_history_on_unsubscribe: bool, // Remove the execution data of unsubscribed protocols
     pub protocol_configs: Vec<ProtocolConfig>, // List of protocol configurations
 }
 
 /// Message used for updating the contract configuration
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct UpdateConfigMsg {
-    pub owner: Option<Addr>,                           // Optional owner update
-    pub max_parallel_claims: Option<u8>,               // Optional max parallel claims update
+    pub owner: Option<Addr>, // Optional owner update, deprecated in favor of ProposeNewOwner
+    pub max_parallel_claims: Option<u8>, // Optional max parallel claims update
+    pub min_claim_interval_seconds: Option<u64>, // Optional min claim interval update
+    pub paused: Option<bool>, // Optional pause flag update
+    pub max_subscriptions_per_user: Option<u32>, // Optional max subscriptions per user update
+    pub max_protocols_per_user_per_call: Option<u32>, // Optional max protocols per user per call update
+    pub fee_first: Option<bool>,                      // Optional fee ordering update
+    pub track_failures: Option<bool>,                 // Optional failure tracking update
+    pub send_denylist: Option<Vec<String>>,           // Optional send denylist update
+    pub require_subscription_for_claim_only: Option<bool>, // Optional claim only subscription check update
+    pub purge_history_on_unsubscribe: Option<bool>, // Optional unsubscribe history purge update
     pub protocol_configs: Option<Vec<ProtocolConfig>>, // Optional protocol configuration update
 }
 
@@ -75,10 +186,14 @@ pub enum ExecuteMsg {
     },
     ClaimAndStake {
         users_protocols: Vec<(String, Vec<String>)>, // List of users and their respective protocols
+        #[serde(default)]
+        deadline: Option<Timestamp>, // Reject the claim if the block time is past this deadline
     },
     ClaimOnly {
         protocol: String,
         users_contracts: Vec<(String, String)>, // (user_address, contract_address)
+        #[serde(default)]
+        deadline: Option<Timestamp>, // Reject the claim if the block time is past this deadline
     },
     Subscribe {
         protocols: Vec<String>, // Protocols to subscribe to
@@ -86,6 +201,39 @@ pub enum ExecuteMsg {
     Unsubscribe {
         protocols: Vec<String>, // Protocols to unsubscribe from
     },
+    PruneEmptySubscriptions {
+        #[serde(default)]
+        start_after: Option<String>, // Address after which the scan starts, from next_start_after
+        limit: u32, // Maximum number of entries to visit in this call
+    },
+    WithdrawFees {
+        denom: String,           // Denomination of the accrued fees
+        amount: Option<Uint128>, // Amount to withdraw, defaults to all accrued fees
+        to_address: String,      // Recipient of the withdrawn fees
+    },
+    RetryFees {
+        users_protocols: Vec<(String, String)>, // (user_address, protocol) with failed fee sends
+    },
+    GcPending {
+        older_than: Timestamp, // Remove pending reply data saved before this time
+        limit: u32,            // Maximum number of entries to remove in this call
+    },
+    DeleteProtocol {
+        protocol: String, // Protocol to remove from the config and the subscriptions
+        #[serde(default)]
+        start_after: Option<String>, // Resumes the subscription prune after this address, from next_start_after
+        #[serde(default)]
+        limit: Option<u32>, // Maximum number of subscriptions visited in this call, defaults to 100
+    },
+    Offboard {}, // Removes the subscriptions and execution history of the sender
+    OffboardUser {
+        user: String, // User whose subscriptions and execution history are removed
+    },
+    RevalidateConfigs {}, // Reports the stored protocol configs that no longer pass validation
+    ProposeNewOwner {
+        new_owner: String, // Address that becomes the owner once it accepts
+    },
+    AcceptOwnership {}, // Called by the proposed owner to complete the transfer
 }
 
 /// Enum for defining the available contract queries
@@ -96,13 +244,75 @@ pub enum QueryMsg {
     #[returns(ConfigResponse)]
     Config {},
 
-    /// Returns the list of all subscriptions (address, [protocols])
+    /// Returns the configuration of a single protocol
+    #[returns(ProtocolConfigResponse)]
+    GetProtocolConfig { protocol: String },
+
+    /// Returns the subscriptions (address, [protocols]) ordered by address, one page at a time
     #[returns(GetSubscriptionsResponse)]
-    GetSubscriptions {},
+    GetSubscriptions {
+        start_after: Option<String>, // Address after which the page starts
+        limit: Option<u32>,          // Page size, capped at 30
+    },
 
     /// Returns the list of protocols a specific address is subscribed to
     #[returns(GetSubscribedProtocolsResponse)]
     GetSubscribedProtocols { user_address: String },
+
+    /// Returns whether the contract is paused
+    #[returns(IsPausedResponse)]
+    IsPaused {},
+
+    /// Returns the config, lifetime stats, and pause flag in a single query
+    #[returns(OverviewResponse)]
+    Overview {},
+
+    /// Returns whether a user is subscribed to a specific protocol
+    #[returns(IsSubscribedResponse)]
+    IsSubscribed {
+        user_address: String,
+        protocol: String,
+    },
+
+    /// Returns the next DAO_DAO claim id used for a user on a protocol
+    #[returns(NextClaimIdResponse)]
+    NextClaimId {
+        user_address: String,
+        protocol: String,
+    },
+
+    /// Returns the rewards a user can currently claim on a protocol
+    #[returns(PreviewRewardsResponse)]
+    PreviewRewards {
+        user_address: String,
+        protocol: String,
+    },
+
+    /// Returns the fee actually charged on a protocol, after all overrides, the same for every user
+    #[returns(EffectiveFeeResponse)]
+    EffectiveFee {
+        #[serde(default)]
+        user_address: String, // Accepted for compatibility only, the fee does not depend on the user
+        protocol: String,
+    },
+
+    /// Dry runs the validation `UpdateConfig` applies to a protocol config, without saving it
+    #[returns(ValidateProtocolConfigResponse)]
+    ValidateProtocolConfig { config: Box<ProtocolConfig> },
+
+    /// Returns the first unexpected reward denom observed in a claim of a protocol
+    #[returns(DenomDriftResponse)]
+    DenomDrift { protocol: String },
+
+    /// Returns the number of configured protocols
+    #[returns(ProtocolCountResponse)]
+    ProtocolCount {},
+
+    /// Returns the fees charged on a hypothetical batch of claimed amounts, for any user
+    #[returns(QuoteBatchFeesResponse)]
+    QuoteBatchFees {
+        items: Vec<(String, Uint128)>, // (protocol, amount claimed in its reward denom)
+    },
 }
 
 /// Response structure for the config query
@@ -110,9 +320,24 @@ pub enum QueryMsg {
 pub struct ConfigResponse {
     pub owner: Addr,
     pub max_parallel_claims: u8,
+    pub min_claim_interval_seconds: u64,
+    pub max_subscriptions_per_user: u32,
+    pub max_protocols_per_user_per_call: u32,
+    pub fee_first: bool,
+    pub track_failures: bool,
+    pub send_denylist: Vec<String>,
+    pub require_subscription_for_claim_only: bool,
+    pub purge_history_on_unsubscribe: bool,
+    pub pending_owner: Option<Addr>,
     pub protocol_configs: Vec<ProtocolConfig>,
 }
 
+/// Response structure for the GetProtocolConfig query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProtocolConfigResponse {
+    pub config: ProtocolConfig,
+}
+
 /// Response structure for the GetSubscriptions query
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GetSubscriptionsResponse {
@@ -124,6 +349,7 @@ pub struct GetSubscriptionsResponse {
 pub struct ProtocolSubscriptionData {
     pub protocol: String,
     pub last_autoclaim: Option<u64>, // Timestamp of the last autoclaim, or None if never executed
+    pub last_failed_at: Option<u64>, // Timestamp of the last failed claim since the last success
 }
 
 /// Response structure for the GetSubscribedProtocols query
@@ -131,3 +357,103 @@ pub struct ProtocolSubscriptionData {
 pub struct GetSubscribedProtocolsResponse {
     pub protocols: Vec<ProtocolSubscriptionData>, // List of protocols with the last autoclaim timestamp for a specific user
 }
+
+/// Response structure for the IsPaused query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IsPausedResponse {
+    pub paused: bool,
+}
+
+/// Lifetime counters of the claims processed by the contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Stats {
+    pub claims_succeeded: u64,
+    pub claims_failed: u64,
+}
+
+/// Response structure for the Overview query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OverviewResponse {
+    pub config: ConfigResponse,
+    pub stats: Stats,
+    pub paused: bool,
+}
+
+/// Response structure for the IsSubscribed query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IsSubscribedResponse {
+    pub subscribed: bool,
+}
+
+/// Response structure for the DenomDrift query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomDriftResponse {
+    pub observed_denom: Option<String>, // None while every claim paid the configured reward denom
+}
+
+/// Response structure for the ProtocolCount query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProtocolCountResponse {
+    pub count: u32,
+}
+
+/// Response structure for the QuoteBatchFees query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct QuoteBatchFeesResponse {
+    pub fees: Vec<(String, Uint128)>, // Fee per protocol, in order of first appearance in the batch
+    pub total: Uint128,               // Sum of the fees of every protocol
+}
+
+/// Response structure for the ValidateProtocolConfig query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidateProtocolConfigResponse {
+    pub valid: bool,
+    pub errors: Vec<String>, // Every problem found, empty when valid
+}
+
+/// Data optionally returned by a claim contract, used to cross-check the claimed amount
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimResponseData {
+    pub amount: Uint128, // Amount the claim contract reports as paid out
+}
+
+/// Data returned by `ClaimAndStake`, summarizing the batch at dispatch time.
+/// Claim, fee and stake results are only known in the replies and are reported as events.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimAndStakeResponseData {
+    pub dispatched: u32,                // Pairs for which a claim was dispatched
+    pub ignored: Vec<(String, String)>, // User and protocol pairs that were skipped
+}
+
+/// Part of the FIN market `config` query response listing the two market denoms
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FinConfigData {
+    pub denoms: Vec<String>,
+}
+
+/// Response expected from a claim contract to a `pending_rewards_query`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingRewardsData {
+    pub amount: Uint128,
+}
+
+/// Response structure for the PreviewRewards query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PreviewRewardsResponse {
+    pub amount: Uint128, // Rewards claimable right now
+    pub denom: String,   // Denomination of the rewards
+}
+
+/// Response structure for the NextClaimId query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NextClaimIdResponse {
+    pub claim_id: Option<u64>, // None when the protocol does not use claim ids
+}
+
+/// Response structure for the EffectiveFee query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EffectiveFeeResponse {
+    pub percentage: Decimal, // Percentage of the claimed rewards charged as fee
+    pub exempt: bool,        // Whether the protocol charges no fee at all
+    pub destination: String, // Address receiving the fee
+}
@@ -1,14 +1,42 @@
-use cosmwasm_std::{Addr, Timestamp, Uint128};
+use cosmwasm_std::{Addr, Coin, Timestamp, Uint128};
 use cw_storage_plus::{Item, Map};
 use serde::{Deserialize, Serialize};
 
-use crate::msg::ProtocolConfig;
+use crate::msg::{ProtocolConfig, Stats};
 
 /// Stores general AutoClaimer configuration
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Config {
     pub owner: Addr, // Owner is now part of the overall configuration
     pub max_parallel_claims: u8,
+    #[serde(default)]
+    pub min_claim_interval_seconds: u64, // Minimum seconds between claims of a user/protocol (0 = disabled)
+    #[serde(default)]
+    pub paused: bool, // When set, keeper claims are rejected
+    #[serde(default)]
+    pub max_subscriptions_per_user: u32, // Maximum protocols a user can subscribe to (0 = unlimited)
+    #[serde(default)]
+    pub max_protocols_per_user_per_call: u32, // Maximum protocols per user in a single claim call (0 = unlimited)
+    #[serde(default = "default_fee_first")]
+    pub fee_first: bool, // Charge the fee before the stake/swap of the net amount
+    #[serde(default)]
+    pub track_failures: bool, // Record the time of failed claims in the execution data
+    #[serde(default)]
+    pub send_denylist: Vec<String>, // Denoms the contract never sends
+    #[serde(default = "default_require_subscription_for_claim_only")]
+    pub require_subscription_for_claim_only: bool, // Skip claim only pairs whose user is not subscribed
+    #[serde(default)]
+    pub purge_history_on_unsubscribe: bool, // Remove the execution data of unsubscribed protocols
+    #[serde(default)]
+    pub pending_owner: Option<Addr>, // Owner proposed with ProposeNewOwner, until it accepts
+}
+
+pub(crate) fn default_fee_first() -> bool {
+    true
+}
+
+pub(crate) fn default_require_subscription_for_claim_only() -> bool {
+    true
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
@@ -16,20 +44,63 @@ pub const CONFIG: Item<Config> = Item::new("config");
 /// Stores the configuration for each protocol, accessible by its name (String).
 pub const PROTOCOL_CONFIG: Map<&str, ProtocolConfig> = Map::new("protocol_config");
 
+/// Stores the number of configured protocols, kept in step with `PROTOCOL_CONFIG` to avoid range scans.
+pub const PROTOCOL_COUNT: Item<u32> = Item::new("protocol_count");
+
 /// Stores user subscriptions, accessible by the user address.
 pub const SUBSCRIPTIONS: Map<&Addr, Vec<String>> = Map::new("subscriptions");
 
 /// Stores operational data like last_autoclaim and potentially other execution metadata
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
 pub struct ExecutionData {
-    pub last_autoclaim: Timestamp,
+    #[serde(default)]
+    pub last_autoclaim: Option<Timestamp>, // None until the first successful claim
+    #[serde(default)]
+    pub last_failed_at: Option<Timestamp>, // Last failed claim since the last success, if tracked
 }
 
 pub const USER_EXECUTION_DATA: Map<(Addr, String), ExecutionData> = Map::new("user_execution_data");
 
+/// Stores the next DAO_DAO claim id of each user and protocol.
+/// Falls back to the `claim_id` of the protocol strategy until the first successful claim.
+pub const NEXT_CLAIM_ID: Map<(Addr, String), u64> = Map::new("next_claim_id");
+
+/// Wraps the data a reply needs with the time it was saved, so orphaned entries can be collected.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PendingData<T> {
+    pub data: T,
+    pub saved_at: Timestamp,
+}
+
 /// Stores user, protocol, and balance_before for each reply_id.
-pub const PENDING_CLAIM_AND_STAKE_DATA: Map<u64, (Addr, String, Uint128)> =
-    Map::new("pending_claim_only_data");
+pub const PENDING_CLAIM_AND_STAKE_DATA: Map<u64, PendingData<(Addr, String, Uint128)>> =
+    Map::new("pending_claim_and_stake_data");
+
+/// Protocol, user, market, and the user balances of the market denoms before the claim.
+pub type ClaimOnlyData = (String, Addr, Addr, Vec<Coin>);
 
-pub const PENDING_CLAIM_ONLY_DATA: Map<u64, (String, Addr, Addr)> =
+/// Stores the claim only data for each claim only reply_id.
+pub const PENDING_CLAIM_ONLY_DATA: Map<u64, PendingData<ClaimOnlyData>> =
     Map::new("pending_claim_only_data");
+
+/// Stores user, protocol, and fee amount for each fee send reply_id.
+pub const PENDING_FEE_DATA: Map<u64, PendingData<(Addr, String, Uint128)>> =
+    Map::new("pending_fee_data");
+
+/// Stores, per denom, the fees the contract received and can send out with `WithdrawFees`.
+pub const ACCRUED_FEES: Map<&str, Uint128> = Map::new("accrued_fees");
+
+/// Stores the fees whose send failed for each user and protocol, pending a `RetryFees`.
+pub const FAILED_FEES: Map<(Addr, String), Uint128> = Map::new("failed_fees");
+
+/// Stores user, protocol, balance_before, and the amount staked for each atomic claim and stake reply_id.
+pub const PENDING_ATOMIC_CLAIM_AND_STAKE_DATA: Map<
+    u64,
+    PendingData<(Addr, String, Uint128, Uint128)>,
+> = Map::new("pending_atomic_claim_and_stake_data");
+
+/// Stores the lifetime claim counters, reported by the Overview query.
+pub const STATS: Item<Stats> = Item::new("stats");
+
+/// Stores, per protocol, the first denom observed in a claim that differs from the configured reward denom.
+pub const DENOM_DRIFT: Map<&str, String> = Map::new("denom_drift");
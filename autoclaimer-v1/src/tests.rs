@@ -1,27 +1,48 @@
 // src/tests.rs
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
-    use crate::contract::{execute, instantiate, query, reply};
+    use crate::contract::{execute, instantiate, migrate, query, reply, reply_id};
+    use crate::error::ContractError;
     use crate::msg::{
-        ConfigResponse, ExecuteMsg, GetSubscribedProtocolsResponse, InstantiateMsg, ProtocolConfig,
-        ProtocolStrategy, QueryMsg, UpdateConfigMsg,
+        ClaimAndStakeResponseData, ClaimResponseData, ClaimWindow, ConfigResponse,
+        DenomDriftResponse, EffectiveFeeResponse, ExecuteMsg, FeeDestination, FinConfigData,
+        GetSubscribedProtocolsResponse, GetSubscriptionsResponse, InstantiateMsg, IsPausedResponse,
+        IsSubscribedResponse, NextClaimIdResponse, OverviewResponse, PendingRewardsData,
+        PreviewRewardsResponse, ProtocolConfig, ProtocolConfigResponse, ProtocolCountResponse,
+        ProtocolStrategy, QueryMsg, QuoteBatchFeesResponse, Stats, UpdateConfigMsg,
+        ValidateProtocolConfigResponse,
     };
+    use crate::state::{
+        PendingData, PENDING_CLAIM_AND_STAKE_DATA, PENDING_CLAIM_ONLY_DATA, PENDING_FEE_DATA,
+        PROTOCOL_CONFIG, SUBSCRIPTIONS, USER_EXECUTION_DATA,
+    };
+    use common::events::MAX_DEBUG_ATTRIBUTE_LEN;
     use common::staking_provider::StakingProvider;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
     use cosmwasm_std::{
-        Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut, Empty, Env, MessageInfo,
-        Response, StdError, Uint128,
+        from_json, to_json_binary, Addr, BankMsg, Binary, Coin, ContractResult, CosmosMsg, Decimal,
+        Deps, DepsMut, Empty, Env, MessageInfo, Order, Reply, Response, StdError, SubMsg,
+        SubMsgResponse, SubMsgResult, SystemResult, Uint128, WasmMsg,
+    };
+    use cw_multi_test::{
+        App, AppBuilder, AppResponse, BankSudo, Contract, ContractWrapper, Executor, SudoMsg,
     };
-    use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+    use cw_storage_plus::{Item, Map};
     use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
 
     // Import the mock structures and functions
-    use crate::mocks::mock_functions::{ClaimMsg, MockClaimExecuteMsg, MockFINExecuteMsg, MockStakeExecuteMsg};
+    use crate::mocks::mock_functions::{
+        ClaimMsg, MockAtomicClaimExecuteMsg, MockClaimExecuteMsg, MockFINExecuteMsg,
+        MockFINQueryMsg, MockStakeExecuteMsg,
+    };
 
     struct Contracts {
         pub autoclaimer: Addr,
         pub claim_contract_success: Addr,
+        pub stake_contract: Addr,
         pub fin_contract_addr: Addr,
     }
 
@@ -32,21 +53,172 @@ mod tests {
 
     fn mock_claim_contract_success() -> Box<dyn Contract<Empty>> {
         let exec_fn = |_deps: DepsMut<Empty>,
+                       _env: Env,
+                       info: MessageInfo,
+                       msg: MockClaimExecuteMsg|
+         -> Result<Response<Empty>, StdError> {
+            let recipient = match msg {
+                MockClaimExecuteMsg::Claim(claim_msg) => claim_msg.user_address,
+                MockClaimExecuteMsg::ClaimToSender {} => info.sender.to_string(),
+            };
+            // Simulate sending tokens to the user
+            Ok(Response::new().add_message(CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient,
+                amount: vec![Coin {
+                    denom: "token1".to_string(), // Must match reward_denom
+                    amount: Uint128::new(1000),  // Simulated amount
+                }],
+            })))
+        };
+
+        let instantiate_fn = |_deps: DepsMut<Empty>,
+                              _env: Env,
+                              _info: MessageInfo,
+                              _msg: Empty|
+         -> Result<Response<Empty>, StdError> { Ok(Response::new()) };
+
+        // Reports the same amount as pending rewards to any query
+        let query_fn = |_deps: Deps<Empty>, _env: Env, _msg: Empty| -> Result<Binary, StdError> {
+            to_json_binary(&PendingRewardsData {
+                amount: Uint128::new(1000),
+            })
+        };
+
+        let contract = ContractWrapper::new_with_empty(exec_fn, instantiate_fn, query_fn);
+        Box::new(contract)
+    }
+
+    fn mock_claim_contract_with_data() -> Box<dyn Contract<Empty>> {
+        let exec_fn = |_deps: DepsMut<Empty>,
+                       _env: Env,
+                       _info: MessageInfo,
+                       msg: MockClaimExecuteMsg|
+         -> Result<Response<Empty>, StdError> {
+            match msg {
+                MockClaimExecuteMsg::Claim(claim_msg) => {
+                    // Pays out 1000 but reports 900 in the response data
+                    Ok(Response::new()
+                        .add_message(CosmosMsg::Bank(BankMsg::Send {
+                            to_address: claim_msg.user_address.clone(),
+                            amount: vec![Coin {
+                                denom: "token1".to_string(),
+                                amount: Uint128::new(1000),
+                            }],
+                        }))
+                        .set_data(to_json_binary(&ClaimResponseData {
+                            amount: Uint128::new(900),
+                        })?))
+                }
+                MockClaimExecuteMsg::ClaimToSender {} => {
+                    Err(StdError::generic_err("Direct claims are not supported"))
+                }
+            }
+        };
+
+        let instantiate_fn = |_deps: DepsMut<Empty>,
+                              _env: Env,
+                              _info: MessageInfo,
+                              _msg: Empty|
+         -> Result<Response<Empty>, StdError> { Ok(Response::new()) };
+
+        let query_fn = |_deps: Deps<Empty>, _env: Env, _msg: Empty| -> Result<Binary, StdError> {
+            Ok(Binary::default())
+        };
+
+        let contract = ContractWrapper::new_with_empty(exec_fn, instantiate_fn, query_fn);
+        Box::new(contract)
+    }
+
+    fn mock_reward_denom_contract() -> Box<dyn Contract<Empty>> {
+        let exec_fn = |_deps: DepsMut<Empty>,
+                       _env: Env,
+                       _info: MessageInfo,
+                       _msg: Empty|
+         -> Result<Response<Empty>, StdError> { Ok(Response::new()) };
+
+        let instantiate_fn = |_deps: DepsMut<Empty>,
+                              _env: Env,
+                              _info: MessageInfo,
+                              _msg: Empty|
+         -> Result<Response<Empty>, StdError> { Ok(Response::new()) };
+
+        // Answers any query with the reward denom
+        let query_fn = |_deps: Deps<Empty>, _env: Env, _msg: Empty| -> Result<Binary, StdError> {
+            to_json_binary("token1")
+        };
+
+        let contract = ContractWrapper::new_with_empty(exec_fn, instantiate_fn, query_fn);
+        Box::new(contract)
+    }
+
+    /// Coins paid out on every claim by the configurable mock claim contract
+    const MOCK_PAYOUT: Item<Vec<Coin>> = Item::new("payout");
+
+    fn mock_claim_contract_configurable() -> Box<dyn Contract<Empty>> {
+        let exec_fn = |deps: DepsMut<Empty>,
                        _env: Env,
                        _info: MessageInfo,
                        msg: MockClaimExecuteMsg|
          -> Result<Response<Empty>, StdError> {
             match msg {
                 MockClaimExecuteMsg::Claim(claim_msg) => {
-                    // Simulate sending tokens to the user
                     Ok(Response::new().add_message(CosmosMsg::Bank(BankMsg::Send {
-                        to_address: claim_msg.user_address.clone(),
-                        amount: vec![Coin {
-                            denom: "token1".to_string(), // Must match reward_denom
-                            amount: Uint128::new(1000),  // Simulated amount
-                        }],
+                        to_address: claim_msg.user_address,
+                        amount: MOCK_PAYOUT.load(deps.storage)?,
                     })))
                 }
+                MockClaimExecuteMsg::ClaimToSender {} => {
+                    Err(StdError::generic_err("Direct claims are not supported"))
+                }
+            }
+        };
+
+        let instantiate_fn = |deps: DepsMut<Empty>,
+                              _env: Env,
+                              _info: MessageInfo,
+                              payout: Vec<Coin>|
+         -> Result<Response<Empty>, StdError> {
+            MOCK_PAYOUT.save(deps.storage, &payout)?;
+            Ok(Response::new())
+        };
+
+        let query_fn = |_deps: Deps<Empty>, _env: Env, _msg: Empty| -> Result<Binary, StdError> {
+            Ok(Binary::default())
+        };
+
+        let contract = ContractWrapper::new_with_empty(exec_fn, instantiate_fn, query_fn);
+        Box::new(contract)
+    }
+
+    fn mock_atomic_claim_contract() -> Box<dyn Contract<Empty>> {
+        let exec_fn = |_deps: DepsMut<Empty>,
+                       _env: Env,
+                       _info: MessageInfo,
+                       msg: MockAtomicClaimExecuteMsg|
+         -> Result<Response<Empty>, StdError> {
+            match msg {
+                MockAtomicClaimExecuteMsg::ClaimAndStake(claim_and_stake_msg) => {
+                    // Simulate claiming 1000 token1 and staking part of it, what is left goes to the user
+                    let stake_amount = claim_and_stake_msg.stake.amount;
+                    Ok(Response::new()
+                        .add_message(CosmosMsg::Bank(BankMsg::Send {
+                            to_address: claim_and_stake_msg.user_address,
+                            amount: vec![Coin {
+                                denom: "token1".to_string(),
+                                amount: Uint128::new(1000) - stake_amount,
+                            }],
+                        }))
+                        .add_message(WasmMsg::Execute {
+                            contract_addr: claim_and_stake_msg.stake_contract_address,
+                            msg: to_json_binary(&MockStakeExecuteMsg::Stake(
+                                claim_and_stake_msg.stake,
+                            ))?,
+                            funds: vec![Coin {
+                                denom: "token1".to_string(),
+                                amount: stake_amount,
+                            }],
+                        }))
+                }
             }
         };
 
@@ -56,8 +228,11 @@ mod tests {
                               _msg: Empty|
          -> Result<Response<Empty>, StdError> { Ok(Response::new()) };
 
+        // Reports the claimable amount as pending rewards to any query
         let query_fn = |_deps: Deps<Empty>, _env: Env, _msg: Empty| -> Result<Binary, StdError> {
-            Ok(Binary::default())
+            to_json_binary(&PendingRewardsData {
+                amount: Uint128::new(1000),
+            })
         };
 
         let contract = ContractWrapper::new_with_empty(exec_fn, instantiate_fn, query_fn);
@@ -131,15 +306,39 @@ mod tests {
     }
 
     fn mock_fin_contract() -> Box<dyn Contract<Empty>> {
-        let exec_fn = |_deps: DepsMut<Empty>,
-                       _env: Env,
-                       _info: MessageInfo,
+        let exec_fn = |deps: DepsMut<Empty>,
+                       env: Env,
+                       info: MessageInfo,
                        msg: MockFINExecuteMsg|
          -> Result<Response<Empty>, StdError> {
             match msg {
-                MockFINExecuteMsg::WithdrawOrders {} => {
-                    // Simulate success
-                    Ok(Response::new())
+                MockFINExecuteMsg::WithdrawOrders(claim_msg) => {
+                    // Simulate filled orders paying out whatever the market holds
+                    let filled = deps.querier.query_all_balances(env.contract.address)?;
+                    if filled.is_empty() {
+                        return Ok(Response::new());
+                    }
+                    Ok(Response::new().add_message(CosmosMsg::Bank(BankMsg::Send {
+                        to_address: claim_msg.user_address,
+                        amount: filled,
+                    })))
+                }
+                MockFINExecuteMsg::Swap(swap_msg) => {
+                    // Simulate a 1:1 swap of the offered funds into usdc
+                    let offered: Uint128 = info.funds.iter().map(|coin| coin.amount).sum();
+                    if swap_msg
+                        .min_return
+                        .is_some_and(|min_return| offered < min_return)
+                    {
+                        return Err(StdError::generic_err("Return below min_return"));
+                    }
+                    Ok(Response::new().add_message(CosmosMsg::Bank(BankMsg::Send {
+                        to_address: swap_msg.user_address,
+                        amount: vec![Coin {
+                            denom: "usdc".to_string(),
+                            amount: offered,
+                        }],
+                    })))
                 }
             }
         };
@@ -150,15 +349,125 @@ mod tests {
                               _msg: Empty|
          -> Result<Response<Empty>, StdError> { Ok(Response::new()) };
 
-        let query_fn = |_deps: Deps<Empty>, _env: Env, _msg: Empty| -> Result<Binary, StdError> {
-            Ok(Binary::default())
-        };
+        let query_fn =
+            |_deps: Deps<Empty>, _env: Env, msg: MockFINQueryMsg| -> Result<Binary, StdError> {
+                match msg {
+                    MockFINQueryMsg::Config {} => to_json_binary(&FinConfigData {
+                        denoms: vec!["token1".to_string(), "usdc".to_string()],
+                    }),
+                }
+            };
 
         let contract = ContractWrapper::new_with_empty(exec_fn, instantiate_fn, query_fn);
 
         Box::new(contract)
     }
 
+    /// Collects the values of `key` from every autoclaimer event with the given action.
+    fn event_attribute_values(res: &AppResponse, action: &str, key: &str) -> Vec<String> {
+        res.events
+            .iter()
+            .filter(|event| event.ty == "wasm-autorujira.autoclaimer")
+            .filter(|event| {
+                event
+                    .attributes
+                    .iter()
+                    .any(|attr| attr.key == "action" && attr.value == action)
+            })
+            .flat_map(|event| {
+                event
+                    .attributes
+                    .iter()
+                    .filter(|attr| attr.key == key)
+                    .map(|attr| attr.value.clone())
+            })
+            .collect()
+    }
+
+    fn mint(app: &mut App, to_address: &Addr, denom: &str, amount: u128) {
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: to_address.to_string(),
+            amount: vec![Coin {
+                denom: denom.to_string(),
+                amount: Uint128::new(amount),
+            }],
+        }))
+        .unwrap();
+    }
+
+    /// Builds an `UpdateConfigMsg` changing nothing, for tests to override with struct update syntax.
+    fn default_update_config() -> UpdateConfigMsg {
+        UpdateConfigMsg {
+            owner: None,
+            max_parallel_claims: None,
+            min_claim_interval_seconds: None,
+            paused: None,
+            max_subscriptions_per_user: None,
+            max_protocols_per_user_per_call: None,
+            fee_first: None,
+            track_failures: None,
+            send_denylist: None,
+            require_subscription_for_claim_only: None,
+            purge_history_on_unsubscribe: None,
+            protocol_configs: None,
+        }
+    }
+
+    /// Builds the `InstantiateMsg` of an `owner` owned contract with the default settings and no protocols.
+    fn default_instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            owner: Addr::unchecked("owner"),
+            max_parallel_claims: 5,
+            min_claim_interval_seconds: 0,
+            max_subscriptions_per_user: 0,
+            max_protocols_per_user_per_call: 0,
+            fee_first: true,
+            track_failures: false,
+            send_denylist: vec![],
+            require_subscription_for_claim_only: true,
+            purge_history_on_unsubscribe: false,
+            protocol_configs: vec![],
+        }
+    }
+
+    /// Builds a protocol config charging a 1% fee sent to `feeaddress1`, with every option unset.
+    fn protocol_config(protocol: &str, strategy: ProtocolStrategy) -> ProtocolConfig {
+        ProtocolConfig {
+            protocol: protocol.to_string(),
+            fee_percentage: Decimal::percent(1),
+            fee_address: "feeaddress1".to_string(),
+            fee_destination: FeeDestination::Send {},
+            strategy,
+            min_claim_interval: None,
+            fee_percentages: vec![],
+            pending_rewards_query: None,
+            min_claim_reward: Uint128::zero(),
+            direct_claim: false,
+            claim_window: None,
+            min_claim_amount: None,
+        }
+    }
+
+    /// Builds a `protocol_config` claiming token1 from a CW_REWARDS contract and staking it.
+    fn claim_and_stake_protocol(
+        protocol: &str,
+        claim_contract: &Addr,
+        stake_contract: &Addr,
+    ) -> ProtocolConfig {
+        protocol_config(
+            protocol,
+            ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+                provider: StakingProvider::CW_REWARDS,
+                claim_contract_address: claim_contract.to_string(),
+                stake_contract_address: stake_contract.to_string(),
+                reward_denom: "token1".to_string(),
+                claim_id: None,
+                reward_denom_query: None,
+                atomic: false,
+            },
+        )
+    }
+
     fn setup() -> (App, Contracts) {
         let mut app = AppBuilder::default().build(|_router, _api, _storage| {});
 
@@ -221,40 +530,39 @@ mod tests {
 
         // Use these addresses in the InstantiateMsg
         let instantiate_msg = InstantiateMsg {
-            owner: owner.clone(),
-            max_parallel_claims: 5,
             protocol_configs: vec![
+                claim_and_stake_protocol(
+                    "protocol1",
+                    &claim_contract_success_addr,
+                    &stake_contract_addr,
+                ),
                 ProtocolConfig {
-                    protocol: "protocol1".to_string(),
-                    fee_percentage: Decimal::percent(1),
-                    fee_address: "feeaddress1".to_string(),
-                    strategy: ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
-                        provider: StakingProvider::CW_REWARDS,
-                        claim_contract_address: claim_contract_success_addr.to_string(),
-                        stake_contract_address: stake_contract_addr.to_string(),
-                        reward_denom: "token1".to_string(),
-                    },
-                },
-                ProtocolConfig {
-                    protocol: "protocol2".to_string(),
-                    fee_percentage: Decimal::percent(1),
                     fee_address: "feeaddress2".to_string(),
-                    strategy: ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
-                        provider: StakingProvider::CW_REWARDS,
-                        claim_contract_address: claim_contract_failure_addr.to_string(),
-                        stake_contract_address: stake_contract_addr.to_string(),
-                        reward_denom: "token2".to_string(),
-                    },
+                    ..protocol_config(
+                        "protocol2",
+                        ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+                            provider: StakingProvider::CW_REWARDS,
+                            claim_contract_address: claim_contract_failure_addr.to_string(),
+                            stake_contract_address: stake_contract_addr.to_string(),
+                            reward_denom: "token2".to_string(),
+                            claim_id: None,
+                            reward_denom_query: None,
+                            atomic: false,
+                        },
+                    )
                 },
                 ProtocolConfig {
-                    protocol: "FIN".to_string(),
                     fee_percentage: Decimal::zero(), // Assuming no fee
                     fee_address: "".to_string(),
-                    strategy: ProtocolStrategy::ClaimOnlyFIN {
-                        supported_markets: vec![fin_contract_addr.to_string()],
-                    },
+                    ..protocol_config(
+                        "FIN",
+                        ProtocolStrategy::ClaimOnlyFIN {
+                            supported_markets: vec![fin_contract_addr.to_string()],
+                        },
+                    )
                 },
             ],
+            ..default_instantiate_msg()
         };
 
         let autoclaimer_addr = app
@@ -273,6 +581,7 @@ mod tests {
             Contracts {
                 autoclaimer: autoclaimer_addr,
                 claim_contract_success: claim_contract_success_addr,
+                stake_contract: stake_contract_addr,
                 fin_contract_addr,
             },
         )
@@ -305,6 +614,7 @@ mod tests {
         let claim_only_msg = ExecuteMsg::ClaimOnly {
             protocol: "FIN".to_string(),
             users_contracts,
+            deadline: None,
         };
 
         let res = app.execute_contract(
@@ -388,6 +698,7 @@ mod tests {
         let claim_only_msg = ExecuteMsg::ClaimOnly {
             protocol: "FIN".to_string(),
             users_contracts,
+            deadline: None,
         };
 
         let err = app
@@ -413,10 +724,8 @@ mod tests {
         let owner = Addr::unchecked("owner");
         let user = Addr::unchecked("user1");
 
-        use cw_multi_test::BankSudo;
-
         // Ensure the claim contract has enough balance to send tokens
-        app.sudo(cw_multi_test::SudoMsg::Bank(BankSudo::Mint {
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
             to_address: contracts.claim_contract_success.to_string(),
             amount: vec![Coin {
                 denom: "token1".to_string(),
@@ -426,7 +735,7 @@ mod tests {
         .unwrap();
 
         // Ensure the autoclaimer contract has enough balance to send tokens
-        app.sudo(cw_multi_test::SudoMsg::Bank(BankSudo::Mint {
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
             to_address: contracts.autoclaimer.to_string(),
             amount: vec![Coin {
                 denom: "token1".to_string(),
@@ -454,6 +763,7 @@ mod tests {
                 user.to_string(),
                 vec!["protocol1".to_string(), "protocol2".to_string()],
             )],
+            deadline: None,
         };
 
         let res = app.execute_contract(
@@ -573,6 +883,28 @@ mod tests {
         assert_eq!(config.protocol_configs[2].protocol, "protocol2");
     }
 
+    #[test]
+    fn test_instantiate_with_original_fields() {
+        let mut deps = mock_dependencies();
+
+        // Instantiate messages written before the optional settings were added still parse
+        let msg: InstantiateMsg =
+            from_json(br#"{"owner": "owner", "max_parallel_claims": 5, "protocol_configs": []}"#)
+                .unwrap();
+        instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+        let config: ConfigResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.min_claim_interval_seconds, 0);
+        assert_eq!(config.max_subscriptions_per_user, 0);
+        assert_eq!(config.max_protocols_per_user_per_call, 0);
+        assert!(config.fee_first);
+        assert!(!config.track_failures);
+        assert!(config.send_denylist.is_empty());
+        assert!(config.require_subscription_for_claim_only);
+        assert!(!config.purge_history_on_unsubscribe);
+    }
+
     #[test]
     fn test_subscribe_and_query_subscriptions() {
         let (mut app, contracts) = setup();
@@ -642,6 +974,71 @@ mod tests {
         assert_eq!(res.protocols[0].protocol, "protocol2");
     }
 
+    #[test]
+    fn test_max_subscriptions_per_user() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        app.execute_contract(
+            owner,
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    max_subscriptions_per_user: Some(2),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        let subscribe = |app: &mut App, protocols: &[&str]| {
+            app.execute_contract(
+                user.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::Subscribe {
+                    protocols: protocols.iter().map(|p| p.to_string()).collect(),
+                },
+                &[],
+            )
+        };
+
+        // Subscribing up to the cap succeeds
+        subscribe(&mut app, &["protocol1", "protocol2"]).unwrap();
+
+        // Going over the cap is rejected
+        let err = subscribe(&mut app, &["FIN"]).unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("Too many subscriptions, the maximum allowed is 2"));
+
+        // Freeing a slot allows subscribing again
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Unsubscribe {
+                protocols: vec!["protocol1".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+        subscribe(&mut app, &["FIN"]).unwrap();
+
+        let res: GetSubscribedProtocolsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contracts.autoclaimer.clone(),
+                &QueryMsg::GetSubscribedProtocols {
+                    user_address: user.to_string(),
+                },
+            )
+            .unwrap();
+        let protocols: Vec<String> = res.protocols.into_iter().map(|p| p.protocol).collect();
+        assert_eq!(protocols, vec!["protocol2".to_string(), "FIN".to_string()]);
+    }
+
     #[test]
     fn test_unauthorized_claim_and_stake() {
         let (mut app, contracts) = setup();
@@ -659,6 +1056,7 @@ mod tests {
 
         let claim_and_stake_msg = ExecuteMsg::ClaimAndStake {
             users_protocols: vec![(user.to_string(), vec!["protocol1".to_string()])],
+            deadline: None,
         };
         let err = app
             .execute_contract(
@@ -683,7 +1081,7 @@ mod tests {
             config: UpdateConfigMsg {
                 owner: Some(Addr::unchecked("new_owner")),
                 max_parallel_claims: Some(10),
-                protocol_configs: None,
+                ..default_update_config()
             },
         };
         app.execute_contract(
@@ -701,4 +1099,4285 @@ mod tests {
         assert_eq!(config.owner, Addr::unchecked("new_owner"));
         assert_eq!(config.max_parallel_claims, 10);
     }
+
+    #[test]
+    fn test_per_protocol_claim_interval() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        // protocol1 can be claimed every 100 seconds, protocol3 every 1000 seconds
+        let protocol3 = ProtocolConfig {
+            min_claim_interval: Some(1000),
+            ..claim_and_stake_protocol(
+                "protocol3",
+                &contracts.claim_contract_success,
+                &contracts.stake_contract,
+            )
+        };
+        let protocol1 = ProtocolConfig {
+            protocol: "protocol1".to_string(),
+            min_claim_interval: Some(100),
+            ..protocol3.clone()
+        };
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    min_claim_interval_seconds: Some(10), // Overridden by both protocols
+                    protocol_configs: Some(vec![protocol1, protocol3]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        mint(&mut app, &contracts.claim_contract_success, "token1", 5000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 5000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol1".to_string(), "protocol3".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let claim_and_stake_msg = ExecuteMsg::ClaimAndStake {
+            users_protocols: vec![(
+                user.to_string(),
+                vec!["protocol1".to_string(), "protocol3".to_string()],
+            )],
+            deadline: None,
+        };
+
+        // First claim: both protocols are dispatched
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &claim_and_stake_msg,
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            event_attribute_values(&res, "claim", "protocol"),
+            vec!["protocol1", "protocol3"]
+        );
+
+        // 200 seconds later only protocol1 is out of its cooldown
+        app.update_block(|block| block.time = block.time.plus_seconds(200));
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &claim_and_stake_msg,
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            event_attribute_values(&res, "claim", "protocol"),
+            vec!["protocol1"]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_and_stake", "ignored_count"),
+            vec!["1"]
+        );
+
+        // After another 1000 seconds both protocols can be claimed again
+        app.update_block(|block| block.time = block.time.plus_seconds(1000));
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &claim_and_stake_msg,
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            event_attribute_values(&res, "claim", "protocol"),
+            vec!["protocol1", "protocol3"]
+        );
+    }
+
+    #[test]
+    fn test_pause_and_query_is_paused() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        let set_paused = |app: &mut App, paused: bool| {
+            app.execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::UpdateConfig {
+                    config: UpdateConfigMsg {
+                        paused: Some(paused),
+                        ..default_update_config()
+                    },
+                },
+                &[],
+            )
+            .unwrap();
+        };
+        let is_paused = |app: &App| -> bool {
+            let res: IsPausedResponse = app
+                .wrap()
+                .query_wasm_smart(contracts.autoclaimer.clone(), &QueryMsg::IsPaused {})
+                .unwrap();
+            res.paused
+        };
+
+        assert!(!is_paused(&app));
+
+        set_paused(&mut app, true);
+        assert!(is_paused(&app));
+
+        // Keeper claims are rejected while paused
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["protocol1".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(err.root_cause().to_string().contains("Contract is paused"));
+
+        set_paused(&mut app, false);
+        assert!(!is_paused(&app));
+    }
+
+    #[test]
+    fn test_prune_empty_subscriptions() {
+        let (mut app, contracts) = setup();
+
+        // Unsubscribing from every protocol leaves no empty entry behind
+        for user in ["user1", "user2"] {
+            app.execute_contract(
+                Addr::unchecked(user),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::Subscribe {
+                    protocols: vec!["protocol1".to_string()],
+                },
+                &[],
+            )
+            .unwrap();
+        }
+        app.execute_contract(
+            Addr::unchecked("user1"),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Unsubscribe {
+                protocols: vec!["protocol1".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res: GetSubscriptionsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contracts.autoclaimer.clone(),
+                &QueryMsg::GetSubscriptions {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            res.subscriptions,
+            vec![("user2".to_string(), vec!["protocol1".to_string()])]
+        );
+
+        // Only the owner can prune
+        let err = app
+            .execute_contract(
+                Addr::unchecked("user1"),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::PruneEmptySubscriptions {
+                    start_after: None,
+                    limit: 10,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("You have no permissions to execute this function"));
+    }
+
+    #[test]
+    fn test_prune_empty_subscriptions_in_pages() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner_info = mock_info("owner", &[]);
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            owner_info.clone(),
+            default_instantiate_msg(),
+        )
+        .unwrap();
+
+        PROTOCOL_CONFIG
+            .save(
+                deps.as_mut().storage,
+                "protocol1",
+                &claim_and_stake_protocol(
+                    "protocol1",
+                    &Addr::unchecked("claim"),
+                    &Addr::unchecked("stake"),
+                ),
+            )
+            .unwrap();
+
+        // Empty entries left by older deployments, around one user with protocols
+        for (user, protocols) in [
+            ("user1", vec![]),
+            ("user2", vec![]),
+            ("user3", vec!["protocol1".to_string()]),
+            ("user4", vec![]),
+        ] {
+            SUBSCRIPTIONS
+                .save(deps.as_mut().storage, &Addr::unchecked(user), &protocols)
+                .unwrap();
+        }
+
+        let mut prune = |start_after: Option<String>| {
+            let res = execute(
+                deps.as_mut(),
+                env.clone(),
+                owner_info.clone(),
+                ExecuteMsg::PruneEmptySubscriptions {
+                    start_after,
+                    limit: 3,
+                },
+            )
+            .unwrap();
+            let attribute = |key: &str| {
+                res.events[0]
+                    .attributes
+                    .iter()
+                    .find(|attr| attr.key == key)
+                    .map(|attr| attr.value.clone())
+            };
+            (attribute("pruned_count"), attribute("next_start_after"))
+        };
+
+        // The first page visits three entries and tells where to resume
+        assert_eq!(
+            prune(None),
+            (Some("2".to_string()), Some("user3".to_string()))
+        );
+        // The last page is short, nothing is left to visit
+        assert_eq!(
+            prune(Some("user3".to_string())),
+            (Some("1".to_string()), None)
+        );
+
+        let remaining: Vec<Addr> = SUBSCRIPTIONS
+            .keys(deps.as_ref().storage, None, None, Order::Ascending)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(remaining, vec![Addr::unchecked("user3")]);
+    }
+
+    #[test]
+    fn test_claim_and_swap_fin() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        let swap_protocol = protocol_config(
+            "protocol_swap",
+            ProtocolStrategy::ClaimAndSwapFIN {
+                provider: StakingProvider::CW_REWARDS,
+                claim_contract_address: contracts.claim_contract_success.to_string(),
+                reward_denom: "token1".to_string(),
+                fin_market: contracts.fin_contract_addr.to_string(),
+                target_denom: "usdc".to_string(),
+                min_price: None,
+                claim_id: None,
+                reward_denom_query: None,
+            },
+        );
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![swap_protocol]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+        mint(&mut app, &contracts.fin_contract_addr, "usdc", 1000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol_swap".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["protocol_swap".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        // 1000 claimed, 1% fee, the remaining 990 swapped into usdc
+        assert_eq!(
+            event_attribute_values(&res, "claim", "tokens_to_swap"),
+            vec!["990".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "swap", "result"),
+            vec!["ok".to_string()]
+        );
+
+        let usdc = app.wrap().query_balance(&user, "usdc").unwrap();
+        assert_eq!(usdc.amount, Uint128::new(990));
+    }
+
+    #[test]
+    fn test_claim_deadline() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol1".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let now = app.block_info().time;
+        let claim_and_stake = |deadline| ExecuteMsg::ClaimAndStake {
+            users_protocols: vec![(user.to_string(), vec!["protocol1".to_string()])],
+            deadline: Some(deadline),
+        };
+
+        // A submission whose deadline already passed is rejected
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &claim_and_stake(now.minus_seconds(1)),
+                &[],
+            )
+            .unwrap_err();
+        assert!(err.root_cause().to_string().contains("Deadline exceeded"));
+
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimOnly {
+                    protocol: "FIN".to_string(),
+                    users_contracts: vec![(
+                        user.to_string(),
+                        contracts.fin_contract_addr.to_string(),
+                    )],
+                    deadline: Some(now.minus_seconds(1)),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(err.root_cause().to_string().contains("Deadline exceeded"));
+
+        // A submission within its deadline goes through
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &claim_and_stake(now.plus_seconds(60)),
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            event_attribute_values(&res, "claim", "result"),
+            vec!["ok".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_max_protocols_per_user_per_call() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    max_protocols_per_user_per_call: Some(1),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        // The total (2) fits max_parallel_claims, but user1 alone exceeds the per-user cap
+        let err = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(
+                        "user1".to_string(),
+                        vec!["protocol1".to_string(), "protocol2".to_string()],
+                    )],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("Too many protocols for user user1, the maximum allowed per call is 1"));
+    }
+
+    #[test]
+    fn test_withdraw_fees() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let treasury = Addr::unchecked("treasury");
+
+        // Route the protocol fees to the contract itself
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![ProtocolConfig {
+                        fee_address: contracts.autoclaimer.to_string(),
+                        ..claim_and_stake_protocol(
+                            "protocol1",
+                            &contracts.claim_contract_success,
+                            &contracts.stake_contract,
+                        )
+                    }]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        // The contract balance also holds funds that are not fees
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+        app.execute_contract(
+            Addr::unchecked("user1"),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol1".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::ClaimAndStake {
+                users_protocols: vec![("user1".to_string(), vec!["protocol1".to_string()])],
+                deadline: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        let withdraw = |amount| ExecuteMsg::WithdrawFees {
+            denom: "token1".to_string(),
+            amount,
+            to_address: treasury.to_string(),
+        };
+
+        // Only the owner can withdraw
+        let err = app
+            .execute_contract(
+                Addr::unchecked("user1"),
+                contracts.autoclaimer.clone(),
+                &withdraw(None),
+                &[],
+            )
+            .unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("You have no permissions"));
+
+        // Only the 10 token1 of accrued fees can leave, not the rest of the balance
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &withdraw(Some(Uint128::new(300))),
+                &[],
+            )
+            .unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("No fees to withdraw in token1"));
+
+        // Partial withdrawal
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &withdraw(Some(Uint128::new(4))),
+            &[],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(&treasury, "token1").unwrap();
+        assert_eq!(balance.amount, Uint128::new(4));
+
+        // Omitting the amount withdraws the remaining accrued fees
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &withdraw(None),
+            &[],
+        )
+        .unwrap();
+        let balance = app.wrap().query_balance(&treasury, "token1").unwrap();
+        assert_eq!(balance.amount, Uint128::new(10));
+
+        // Nothing left to withdraw
+        let err = app
+            .execute_contract(owner, contracts.autoclaimer.clone(), &withdraw(None), &[])
+            .unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("No fees to withdraw in token1"));
+    }
+
+    #[test]
+    fn test_effective_fee() {
+        let (app, contracts) = setup();
+
+        let fee: EffectiveFeeResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contracts.autoclaimer.clone(),
+                &QueryMsg::EffectiveFee {
+                    user_address: "user1".to_string(),
+                    protocol: "protocol1".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            fee,
+            EffectiveFeeResponse {
+                percentage: Decimal::percent(1),
+                exempt: false,
+                destination: "feeaddress1".to_string(),
+            }
+        );
+
+        // Unknown protocols have no fee to resolve
+        let res: Result<EffectiveFeeResponse, _> = app.wrap().query_wasm_smart(
+            contracts.autoclaimer,
+            &QueryMsg::EffectiveFee {
+                user_address: "user1".to_string(),
+                protocol: "unknown".to_string(),
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_unsubscribe_from_deleted_protocol() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            InstantiateMsg {
+                protocol_configs: vec![protocol_config(
+                    "protocol1",
+                    ProtocolStrategy::ClaimOnlyFIN {
+                        supported_markets: vec![],
+                    },
+                )],
+                ..default_instantiate_msg()
+            },
+        )
+        .unwrap();
+
+        let user_info = mock_info("user1", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            user_info.clone(),
+            ExecuteMsg::Subscribe {
+                protocols: vec!["protocol1".to_string()],
+            },
+        )
+        .unwrap();
+
+        // The protocol is removed from the config after the user subscribed
+        PROTOCOL_CONFIG.remove(deps.as_mut().storage, "protocol1");
+
+        // Unsubscribing from it, and from a protocol never subscribed to, succeeds
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            user_info.clone(),
+            ExecuteMsg::Unsubscribe {
+                protocols: vec!["protocol1".to_string(), "never_subscribed".to_string()],
+            },
+        )
+        .unwrap();
+        assert!(!SUBSCRIPTIONS.has(deps.as_ref().storage, &Addr::unchecked("user1")));
+
+        // Malformed protocol names are still rejected
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            user_info,
+            ExecuteMsg::Unsubscribe {
+                protocols: vec!["".to_string()],
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Unsupported protocol"));
+    }
+
+    #[test]
+    fn test_next_claim_id_increments() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        let dao_protocol = protocol_config(
+            "protocol_dao",
+            ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+                provider: StakingProvider::DAO_DAO,
+                claim_contract_address: contracts.claim_contract_success.to_string(),
+                stake_contract_address: contracts.stake_contract.to_string(),
+                reward_denom: "token1".to_string(),
+                claim_id: Some(5),
+                reward_denom_query: None,
+                atomic: false,
+            },
+        );
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![dao_protocol]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        let next_claim_id = |app: &App, protocol: &str| -> Option<u64> {
+            let res: NextClaimIdResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contracts.autoclaimer.clone(),
+                    &QueryMsg::NextClaimId {
+                        user_address: user.to_string(),
+                        protocol: protocol.to_string(),
+                    },
+                )
+                .unwrap();
+            res.claim_id
+        };
+
+        // Defaults to the strategy claim id, and CW_REWARDS protocols have none
+        assert_eq!(next_claim_id(&app, "protocol_dao"), Some(5));
+        assert_eq!(next_claim_id(&app, "protocol1"), None);
+
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol_dao".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        app.execute_contract(
+            owner,
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::ClaimAndStake {
+                users_protocols: vec![(user.to_string(), vec!["protocol_dao".to_string()])],
+                deadline: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(next_claim_id(&app, "protocol_dao"), Some(6));
+    }
+
+    #[test]
+    fn test_claim_reply_data_cross_check() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        let code_id = app.store_code(mock_claim_contract_with_data());
+        let claim_contract = app
+            .instantiate_contract(
+                code_id,
+                owner.clone(),
+                &Empty {},
+                &[],
+                "Mock Claim Contract With Data",
+                None,
+            )
+            .unwrap();
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![claim_and_stake_protocol(
+                        "protocol_data",
+                        &claim_contract,
+                        &contracts.stake_contract,
+                    )]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        mint(&mut app, &claim_contract, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol_data".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["protocol_data".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        assert_eq!(
+            event_attribute_values(&res, "claim", "tokens_claimed"),
+            vec!["1000".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim", "reported_amount"),
+            vec!["900".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim", "amount_discrepancy"),
+            vec!["true".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_fee_and_stake_ordering() {
+        for fee_first in [true, false] {
+            let (mut app, contracts) = setup();
+            let owner = Addr::unchecked("owner");
+            let user = Addr::unchecked("user1");
+
+            app.execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::UpdateConfig {
+                    config: UpdateConfigMsg {
+                        fee_first: Some(fee_first),
+                        ..default_update_config()
+                    },
+                },
+                &[],
+            )
+            .unwrap();
+
+            mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+            mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+            app.execute_contract(
+                user.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::Subscribe {
+                    protocols: vec!["protocol1".to_string()],
+                },
+                &[],
+            )
+            .unwrap();
+
+            let res = app
+                .execute_contract(
+                    owner,
+                    contracts.autoclaimer.clone(),
+                    &ExecuteMsg::ClaimAndStake {
+                        users_protocols: vec![(user.to_string(), vec!["protocol1".to_string()])],
+                        deadline: None,
+                    },
+                    &[],
+                )
+                .unwrap();
+
+            // Both complete regardless of the order
+            assert_eq!(
+                event_attribute_values(&res, "charge_fee", "result"),
+                vec!["ok".to_string()]
+            );
+            assert_eq!(
+                event_attribute_values(&res, "stake", "result"),
+                vec!["ok".to_string()]
+            );
+
+            // The actions run in the configured order
+            let actions: Vec<String> = res
+                .events
+                .iter()
+                .filter(|event| event.ty == "wasm-autorujira.autoclaimer")
+                .flat_map(|event| event.attributes.iter())
+                .filter(|attr| {
+                    attr.key == "action" && (attr.value == "charge_fee" || attr.value == "stake")
+                })
+                .map(|attr| attr.value.clone())
+                .collect();
+            let expected = if fee_first {
+                vec!["charge_fee", "stake"]
+            } else {
+                vec!["stake", "charge_fee"]
+            };
+            assert_eq!(actions, expected);
+
+            // Fee and staked amount add up to the claimed amount
+            let fee = app.wrap().query_balance("feeaddress1", "token1").unwrap();
+            let staked = app
+                .wrap()
+                .query_balance(&contracts.stake_contract, "token1")
+                .unwrap();
+            assert_eq!(fee.amount, Uint128::new(10));
+            assert_eq!(staked.amount, Uint128::new(990));
+        }
+    }
+
+    #[test]
+    fn test_dispatch_events_include_grantee() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol2".to_string(), "FIN".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["protocol2".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_and_stake", "grantee"),
+            vec![contracts.autoclaimer.to_string()]
+        );
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimOnly {
+                    protocol: "FIN".to_string(),
+                    users_contracts: vec![(
+                        user.to_string(),
+                        contracts.fin_contract_addr.to_string(),
+                    )],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_only", "grantee"),
+            vec![contracts.autoclaimer.to_string()]
+        );
+    }
+
+    #[test]
+    fn test_reward_denom_query() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        let code_id = app.store_code(mock_reward_denom_contract());
+        let denom_contract = app
+            .instantiate_contract(
+                code_id,
+                owner.clone(),
+                &Empty {},
+                &[],
+                "Mock Reward Denom Contract",
+                None,
+            )
+            .unwrap();
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![protocol_config(
+                        "protocol_query",
+                        ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+                            provider: StakingProvider::CW_REWARDS,
+                            claim_contract_address: contracts.claim_contract_success.to_string(),
+                            stake_contract_address: contracts.stake_contract.to_string(),
+                            // Superseded by the queried denom
+                            reward_denom: "static_denom".to_string(),
+                            claim_id: None,
+                            reward_denom_query: Some((
+                                denom_contract,
+                                r#"{"reward_denom":{}}"#.to_string(),
+                            )),
+                            atomic: false,
+                        },
+                    )]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol_query".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["protocol_query".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        // The balance diff is computed on the queried denom
+        assert_eq!(
+            event_attribute_values(&res, "claim", "token"),
+            vec!["token1".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim", "tokens_claimed"),
+            vec!["1000".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_reply_id_range_boundary() {
+        // The last slot of a range is usable
+        assert_eq!(reply_id(1000, 999).unwrap(), 1999);
+
+        // The next one would land in the following range and is rejected
+        let err = reply_id(1000, 1000).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Batch too large for the reply ID space, the maximum allowed is 1000"));
+    }
+
+    #[test]
+    fn test_preview_rewards() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+
+        let preview = |app: &App, protocol: &str| {
+            app.wrap().query_wasm_smart::<PreviewRewardsResponse>(
+                contracts.autoclaimer.clone(),
+                &QueryMsg::PreviewRewards {
+                    user_address: "user1".to_string(),
+                    protocol: protocol.to_string(),
+                },
+            )
+        };
+
+        // No pending rewards query configured yet
+        assert!(preview(&app, "protocol1").is_err());
+
+        app.execute_contract(
+            owner,
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![ProtocolConfig {
+                        pending_rewards_query: Some(r#"{"rewards":{"user":"{user}"}}"#.to_string()),
+                        ..claim_and_stake_protocol(
+                            "protocol1",
+                            &contracts.claim_contract_success,
+                            &contracts.stake_contract,
+                        )
+                    }]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(
+            preview(&app, "protocol1").unwrap(),
+            PreviewRewardsResponse {
+                amount: Uint128::new(1000),
+                denom: "token1".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_denom_specific_fee_percentages() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        let code_id = app.store_code(mock_claim_contract_configurable());
+        let token2_claim_contract = app
+            .instantiate_contract(
+                code_id,
+                owner.clone(),
+                &vec![Coin {
+                    denom: "token2".to_string(),
+                    amount: Uint128::new(1000),
+                }],
+                &[],
+                "Mock Claim Contract token2",
+                None,
+            )
+            .unwrap();
+
+        // Base fee of 1%, overridden to 2% for token1 and 5% for token2
+        let denom_protocol = |protocol: &str, claim_contract: &Addr, denom: &str| ProtocolConfig {
+            fee_percentages: vec![
+                ("token1".to_string(), Decimal::percent(2)),
+                ("token2".to_string(), Decimal::percent(5)),
+            ],
+            ..protocol_config(
+                protocol,
+                ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+                    provider: StakingProvider::CW_REWARDS,
+                    claim_contract_address: claim_contract.to_string(),
+                    stake_contract_address: contracts.stake_contract.to_string(),
+                    reward_denom: denom.to_string(),
+                    claim_id: None,
+                    reward_denom_query: None,
+                    atomic: false,
+                },
+            )
+        };
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![
+                        denom_protocol("protocol_a", &contracts.claim_contract_success, "token1"),
+                        denom_protocol("protocol_b", &token2_claim_contract, "token2"),
+                    ]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &token2_claim_contract, "token2", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token2", 1000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol_a".to_string(), "protocol_b".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        app.execute_contract(
+            owner,
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::ClaimAndStake {
+                users_protocols: vec![(
+                    user.to_string(),
+                    vec!["protocol_a".to_string(), "protocol_b".to_string()],
+                )],
+                deadline: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        let token1_fee = app.wrap().query_balance("feeaddress1", "token1").unwrap();
+        let token2_fee = app.wrap().query_balance("feeaddress1", "token2").unwrap();
+        assert_eq!(token1_fee.amount, Uint128::new(20));
+        assert_eq!(token2_fee.amount, Uint128::new(50));
+    }
+
+    #[test]
+    fn test_failed_claim_sets_last_failed_at() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    track_failures: Some(true),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol2".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        // protocol2 uses the failing claim contract
+        app.execute_contract(
+            owner,
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::ClaimAndStake {
+                users_protocols: vec![(user.to_string(), vec!["protocol2".to_string()])],
+                deadline: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res: GetSubscribedProtocolsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contracts.autoclaimer.clone(),
+                &QueryMsg::GetSubscribedProtocols {
+                    user_address: user.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.protocols.len(), 1);
+        assert_eq!(
+            res.protocols[0].last_failed_at,
+            Some(app.block_info().time.seconds())
+        );
+        assert_eq!(res.protocols[0].last_autoclaim, None);
+    }
+
+    #[test]
+    fn test_send_denylist() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    send_denylist: Some(vec!["token1".to_string()]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token2", 1000);
+
+        // Withdrawing a denylisted denom is blocked, other denoms pass the denylist check
+        let withdraw = |denom: &str| ExecuteMsg::WithdrawFees {
+            denom: denom.to_string(),
+            amount: None,
+            to_address: "treasury".to_string(),
+        };
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &withdraw("token1"),
+                &[],
+            )
+            .unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("Denom token1 is not allowed to be sent"));
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &withdraw("token2"),
+                &[],
+            )
+            .unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("No fees to withdraw in token2"));
+
+        // Charging a fee in a denylisted denom is blocked as well
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol1".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+        let err = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["protocol1".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("Denom token1 is not allowed to be sent"));
+    }
+
+    #[test]
+    fn test_fee_destination_stake() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+        let treasury = Addr::unchecked("treasury");
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![ProtocolConfig {
+                        fee_destination: FeeDestination::Stake {
+                            treasury: treasury.clone(),
+                        },
+                        ..claim_and_stake_protocol(
+                            "protocol1",
+                            &contracts.claim_contract_success,
+                            &contracts.stake_contract,
+                        )
+                    }]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        // Mocked sends come out of the contract balance: fee send, fee stake and user stake
+        mint(&mut app, &contracts.autoclaimer, "token1", 2000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol1".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["protocol1".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        // The fee is staked for the treasury through its own submessage
+        assert_eq!(
+            event_attribute_values(&res, "stake_fee", "result"),
+            vec!["ok".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "stake_fee", "msg_id"),
+            vec!["6000".to_string()]
+        );
+
+        // 990 staked for the user and 10 staked for the treasury
+        let staked = app
+            .wrap()
+            .query_balance(contracts.stake_contract.to_string(), "token1")
+            .unwrap();
+        assert_eq!(staked.amount, Uint128::new(1000));
+    }
+
+    #[test]
+    fn test_claim_and_stake_deterministic_reply_ids() {
+        // Runs a batch on a fresh contract and returns the (msg_id, user, protocol) of each claim
+        let claim_mapping = |users_protocols: Vec<(String, Vec<String>)>| {
+            let (mut app, contracts) = setup();
+            let owner = Addr::unchecked("owner");
+            mint(&mut app, &contracts.claim_contract_success, "token1", 2000);
+            mint(&mut app, &contracts.autoclaimer, "token1", 2000);
+
+            for user in ["user1", "user2"] {
+                app.execute_contract(
+                    Addr::unchecked(user),
+                    contracts.autoclaimer.clone(),
+                    &ExecuteMsg::Subscribe {
+                        protocols: vec!["protocol1".to_string(), "protocol2".to_string()],
+                    },
+                    &[],
+                )
+                .unwrap();
+            }
+
+            let res = app
+                .execute_contract(
+                    owner,
+                    contracts.autoclaimer.clone(),
+                    &ExecuteMsg::ClaimAndStake {
+                        users_protocols,
+                        deadline: None,
+                    },
+                    &[],
+                )
+                .unwrap();
+
+            let msg_ids = event_attribute_values(&res, "claim", "msg_id");
+            let addresses = event_attribute_values(&res, "claim", "address");
+            let protocols = event_attribute_values(&res, "claim", "protocol");
+            let mut mapping: Vec<(String, String, String)> = msg_ids
+                .into_iter()
+                .zip(addresses)
+                .zip(protocols)
+                .map(|((msg_id, address), protocol)| (msg_id, address, protocol))
+                .collect();
+            mapping.sort();
+            mapping
+        };
+
+        let ordered = claim_mapping(vec![
+            (
+                "user1".to_string(),
+                vec!["protocol1".to_string(), "protocol2".to_string()],
+            ),
+            (
+                "user2".to_string(),
+                vec!["protocol1".to_string(), "protocol2".to_string()],
+            ),
+        ]);
+        let shuffled = claim_mapping(vec![
+            (
+                "user2".to_string(),
+                vec!["protocol2".to_string(), "protocol1".to_string()],
+            ),
+            (
+                "user1".to_string(),
+                vec!["protocol2".to_string(), "protocol1".to_string()],
+            ),
+        ]);
+
+        assert_eq!(ordered.len(), 4);
+        assert_eq!(ordered, shuffled);
+        assert_eq!(
+            ordered[0],
+            (
+                "1000".to_string(),
+                "user1".to_string(),
+                "protocol1".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_ignored_pairs_attribute_is_capped() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    max_parallel_claims: Some(u8::MAX),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        // None of these users is subscribed, so every pair is ignored
+        let users_protocols: Vec<(String, Vec<String>)> = (0..u8::MAX)
+            .map(|i| {
+                (
+                    format!("unsubscribed_user_with_a_long_address_{}", i),
+                    vec!["protocol1".to_string()],
+                )
+            })
+            .collect();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols,
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        let ignored_pairs =
+            event_attribute_values(&res, "execute_claim_and_stake", "ignored_pairs");
+        assert!(ignored_pairs[0].len() <= MAX_DEBUG_ATTRIBUTE_LEN);
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_and_stake", "truncated"),
+            vec!["true".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_and_stake", "ignored_count"),
+            vec!["255".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_is_subscribed() {
+        let (mut app, contracts) = setup();
+        let user = Addr::unchecked("user1");
+
+        let is_subscribed = |app: &App| -> bool {
+            let res: IsSubscribedResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contracts.autoclaimer.clone(),
+                    &QueryMsg::IsSubscribed {
+                        user_address: "user1".to_string(),
+                        protocol: "protocol1".to_string(),
+                    },
+                )
+                .unwrap();
+            res.subscribed
+        };
+
+        assert!(!is_subscribed(&app));
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol1".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+        assert!(is_subscribed(&app));
+
+        app.execute_contract(
+            user,
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Unsubscribe {
+                protocols: vec!["protocol1".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+        assert!(!is_subscribed(&app));
+    }
+
+    #[test]
+    fn test_failed_fee_send_is_recorded_and_retried() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    fee_first: Some(false),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        // Mocked sends come out of the contract balance: only the stake is covered, so the fee send fails
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 990);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol1".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["protocol1".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        assert_eq!(
+            event_attribute_values(&res, "stake", "result"),
+            vec!["ok".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "charge_fee", "result"),
+            vec!["failed".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "charge_fee", "failed_fee"),
+            vec!["10".to_string()]
+        );
+        let fee_balance = app.wrap().query_balance("feeaddress1", "token1").unwrap();
+        assert_eq!(fee_balance.amount, Uint128::zero());
+
+        // Retry once the fee can be sent
+        mint(&mut app, &contracts.autoclaimer, "token1", 10);
+        let retry_msg = ExecuteMsg::RetryFees {
+            users_protocols: vec![(user.to_string(), "protocol1".to_string())],
+        };
+
+        let err = app
+            .execute_contract(user.clone(), contracts.autoclaimer.clone(), &retry_msg, &[])
+            .unwrap_err();
+        assert!(err.root_cause().to_string().contains("no permissions"));
+
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &retry_msg,
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            event_attribute_values(&res, "retry_fees", "retried_count"),
+            vec!["1".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "charge_fee", "result"),
+            vec!["ok".to_string()]
+        );
+        let fee_balance = app.wrap().query_balance("feeaddress1", "token1").unwrap();
+        assert_eq!(fee_balance.amount, Uint128::new(10));
+
+        // Nothing left to retry
+        let res = app
+            .execute_contract(owner, contracts.autoclaimer.clone(), &retry_msg, &[])
+            .unwrap();
+        assert_eq!(
+            event_attribute_values(&res, "retry_fees", "retried_count"),
+            vec!["0".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_atomic_claim_and_stake() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        let code_id = app.store_code(mock_atomic_claim_contract());
+        let atomic_claim_contract = app
+            .instantiate_contract(
+                code_id,
+                owner.clone(),
+                &Empty {},
+                &[],
+                "Mock Atomic Claim Contract",
+                None,
+            )
+            .unwrap();
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![ProtocolConfig {
+                        pending_rewards_query: Some("{}".to_string()),
+                        ..protocol_config(
+                            "atomic",
+                            ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+                                provider: StakingProvider::CW_REWARDS,
+                                claim_contract_address: atomic_claim_contract.to_string(),
+                                stake_contract_address: contracts.stake_contract.to_string(),
+                                reward_denom: "token1".to_string(),
+                                claim_id: None,
+                                reward_denom_query: None,
+                                atomic: true,
+                            },
+                        )
+                    }]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        mint(&mut app, &atomic_claim_contract, "token1", 1000);
+        // Mocked sends come out of the contract balance: fee send
+        mint(&mut app, &contracts.autoclaimer, "token1", 10);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["atomic".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["atomic".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        // A single submessage claimed and staked, without separate claim or stake replies
+        assert_eq!(
+            event_attribute_values(&res, "claim_and_stake", "msg_id"),
+            vec!["7000".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim_and_stake", "result"),
+            vec!["ok".to_string()]
+        );
+        assert!(event_attribute_values(&res, "claim", "result").is_empty());
+        assert!(event_attribute_values(&res, "stake", "result").is_empty());
+
+        // The fee is what the batch left in the user balance
+        assert_eq!(
+            event_attribute_values(&res, "claim_and_stake", "tokens_claimed"),
+            vec!["1000".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim_and_stake", "tokens_staked"),
+            vec!["990".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim_and_stake", "fee_to_charge"),
+            vec!["10".to_string()]
+        );
+
+        let staked = app
+            .wrap()
+            .query_balance(contracts.stake_contract.to_string(), "token1")
+            .unwrap();
+        assert_eq!(staked.amount, Uint128::new(990));
+        let fee_balance = app.wrap().query_balance("feeaddress1", "token1").unwrap();
+        assert_eq!(fee_balance.amount, Uint128::new(10));
+    }
+
+    #[test]
+    fn test_gc_pending() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner_info = mock_info("owner", &[]);
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            owner_info.clone(),
+            default_instantiate_msg(),
+        )
+        .unwrap();
+
+        let user = Addr::unchecked("user1");
+        let old_entry = PendingData {
+            data: (user.clone(), "protocol1".to_string(), Uint128::zero()),
+            saved_at: env.block.time.minus_seconds(7200),
+        };
+        let fresh_entry = PendingData {
+            data: (user.clone(), "protocol1".to_string(), Uint128::new(10)),
+            saved_at: env.block.time,
+        };
+        PENDING_CLAIM_AND_STAKE_DATA
+            .save(deps.as_mut().storage, 1000, &old_entry)
+            .unwrap();
+        PENDING_FEE_DATA
+            .save(deps.as_mut().storage, 3000, &fresh_entry)
+            .unwrap();
+        // Entry saved before pending data recorded a timestamp
+        Map::<u64, (String, Addr, Addr)>::new("pending_claim_only_data")
+            .save(
+                deps.as_mut().storage,
+                4000,
+                &("FIN".to_string(), user.clone(), Addr::unchecked("market")),
+            )
+            .unwrap();
+
+        let gc_msg = |limit: u32| ExecuteMsg::GcPending {
+            older_than: env.block.time.minus_seconds(3600),
+            limit,
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user1", &[]),
+            gc_msg(10),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized));
+
+        let removed_count = |res: &Response| {
+            res.events[0]
+                .attributes
+                .iter()
+                .find(|attribute| attribute.key == "removed_count")
+                .unwrap()
+                .value
+                .clone()
+        };
+
+        // The limit bounds the work done per call
+        let res = execute(deps.as_mut(), env.clone(), owner_info.clone(), gc_msg(1)).unwrap();
+        assert_eq!(removed_count(&res), "1");
+        let res = execute(deps.as_mut(), env.clone(), owner_info, gc_msg(10)).unwrap();
+        assert_eq!(removed_count(&res), "1");
+
+        // Only the fresh entry is left
+        assert!(PENDING_CLAIM_AND_STAKE_DATA
+            .may_load(deps.as_ref().storage, 1000)
+            .unwrap()
+            .is_none());
+        assert!(PENDING_CLAIM_ONLY_DATA
+            .keys_raw(deps.as_ref().storage, None, None, Order::Ascending)
+            .next()
+            .is_none());
+        assert_eq!(
+            PENDING_FEE_DATA.load(deps.as_ref().storage, 3000).unwrap(),
+            fresh_entry
+        );
+    }
+
+    #[test]
+    fn test_zero_fee_protocol_skips_fee_address() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        // The fee address would fail validation if it were ever used
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![ProtocolConfig {
+                        fee_percentage: Decimal::zero(),
+                        fee_address: "".to_string(),
+                        ..claim_and_stake_protocol(
+                            "zero_fee",
+                            &contracts.claim_contract_success,
+                            &contracts.stake_contract,
+                        )
+                    }]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        let effective_fee: EffectiveFeeResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contracts.autoclaimer.clone(),
+                &QueryMsg::EffectiveFee {
+                    user_address: user.to_string(),
+                    protocol: "zero_fee".to_string(),
+                },
+            )
+            .unwrap();
+        assert!(effective_fee.exempt);
+
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["zero_fee".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["zero_fee".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        assert_eq!(
+            event_attribute_values(&res, "claim", "fee_to_charge"),
+            vec!["0".to_string()]
+        );
+        assert!(event_attribute_values(&res, "charge_fee", "result").is_empty());
+        assert_eq!(
+            event_attribute_values(&res, "stake", "result"),
+            vec!["ok".to_string()]
+        );
+        let staked = app
+            .wrap()
+            .query_balance(contracts.stake_contract.to_string(), "token1")
+            .unwrap();
+        assert_eq!(staked.amount, Uint128::new(1000));
+    }
+
+    #[test]
+    fn test_overview() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol1".to_string(), "protocol2".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        // protocol1 claims successfully and protocol2 fails
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::ClaimAndStake {
+                users_protocols: vec![(
+                    user.to_string(),
+                    vec!["protocol1".to_string(), "protocol2".to_string()],
+                )],
+                deadline: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        app.execute_contract(
+            owner,
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    paused: Some(true),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        let overview: OverviewResponse = app
+            .wrap()
+            .query_wasm_smart(contracts.autoclaimer.clone(), &QueryMsg::Overview {})
+            .unwrap();
+        let config: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(contracts.autoclaimer, &QueryMsg::Config {})
+            .unwrap();
+
+        assert_eq!(overview.config, config);
+        assert_eq!(
+            overview.stats,
+            Stats {
+                claims_succeeded: 1,
+                claims_failed: 1,
+            }
+        );
+        assert!(overview.paused);
+    }
+
+    #[test]
+    fn test_denom_drift_is_recorded() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        // The claim contract also pays a denom the protocol is not configured for
+        let code_id = app.store_code(mock_claim_contract_configurable());
+        let drifting_claim_contract = app
+            .instantiate_contract(
+                code_id,
+                owner.clone(),
+                &vec![Coin::new(1000, "token1"), Coin::new(50, "token2")],
+                &[],
+                "Mock Claim Contract drifting",
+                None,
+            )
+            .unwrap();
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![claim_and_stake_protocol(
+                        "drifting",
+                        &drifting_claim_contract,
+                        &contracts.stake_contract,
+                    )]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        mint(&mut app, &drifting_claim_contract, "token1", 1000);
+        mint(&mut app, &drifting_claim_contract, "token2", 50);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["drifting".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["drifting".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        assert_eq!(
+            event_attribute_values(&res, "denom_drift", "observed_denom"),
+            vec!["token2".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "denom_drift", "expected_denom"),
+            vec!["token1".to_string()]
+        );
+        // The extra denom is left to the user and reported for reconciliation
+        assert_eq!(
+            event_attribute_values(&res, "unhandled_reward", "denom"),
+            vec!["token2".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "unhandled_reward", "amount"),
+            vec!["50".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim", "tokens_claimed"),
+            vec!["1000".to_string()]
+        );
+
+        let drift: DenomDriftResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contracts.autoclaimer,
+                &QueryMsg::DenomDrift {
+                    protocol: "drifting".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(drift.observed_denom, Some("token2".to_string()));
+    }
+
+    #[test]
+    fn test_claim_only_requires_subscription() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        let claim_only_msg = ExecuteMsg::ClaimOnly {
+            protocol: "FIN".to_string(),
+            users_contracts: vec![(user.to_string(), contracts.fin_contract_addr.to_string())],
+            deadline: None,
+        };
+
+        // The user never subscribed, so the pair is skipped
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &claim_only_msg,
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_only", "ignored_count"),
+            vec!["1".to_string()]
+        );
+        assert!(event_attribute_values(&res, "claim", "result").is_empty());
+
+        // With the check disabled the pair is claimed
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    require_subscription_for_claim_only: Some(false),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(owner, contracts.autoclaimer.clone(), &claim_only_msg, &[])
+            .unwrap();
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_only", "ignored_count"),
+            vec!["0".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim", "result"),
+            vec!["ok".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_claim_and_stake_response_data() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol1".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(
+                        user.to_string(),
+                        vec!["protocol1".to_string(), "protocol2".to_string()],
+                    )],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        let data: ClaimAndStakeResponseData = from_json(res.data.unwrap()).unwrap();
+        assert_eq!(
+            data,
+            ClaimAndStakeResponseData {
+                dispatched: 1,
+                ignored: vec![(user.to_string(), "protocol2".to_string())],
+            }
+        );
+    }
+
+    #[test]
+    fn test_unsubscribe_history_retention() {
+        // Claims protocol1 for a user, unsubscribes and returns whether its execution data remains
+        let history_after_unsubscribe = |purge_history_on_unsubscribe: bool| {
+            let (mut app, contracts) = setup();
+            let owner = Addr::unchecked("owner");
+            let user = Addr::unchecked("user1");
+            mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+            mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+            app.execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::UpdateConfig {
+                    config: UpdateConfigMsg {
+                        purge_history_on_unsubscribe: Some(purge_history_on_unsubscribe),
+                        ..default_update_config()
+                    },
+                },
+                &[],
+            )
+            .unwrap();
+
+            app.execute_contract(
+                user.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::Subscribe {
+                    protocols: vec!["protocol1".to_string()],
+                },
+                &[],
+            )
+            .unwrap();
+
+            app.execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["protocol1".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+            let key = (user.clone(), "protocol1".to_string());
+            assert!(USER_EXECUTION_DATA
+                .query(&app.wrap(), contracts.autoclaimer.clone(), key.clone())
+                .unwrap()
+                .is_some_and(|execution_data| execution_data.last_autoclaim.is_some()));
+
+            app.execute_contract(
+                user,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::Unsubscribe {
+                    protocols: vec!["protocol1".to_string()],
+                },
+                &[],
+            )
+            .unwrap();
+
+            USER_EXECUTION_DATA
+                .query(&app.wrap(), contracts.autoclaimer, key)
+                .unwrap()
+        };
+
+        // History is retained by default
+        assert!(history_after_unsubscribe(false).is_some());
+        // And removed when purging is enabled
+        assert!(history_after_unsubscribe(true).is_none());
+    }
+
+    #[test]
+    fn test_validate_protocol_config() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+
+        let valid_config = protocol_config(
+            "protocol3",
+            ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+                provider: StakingProvider::DAO_DAO,
+                claim_contract_address: contracts.claim_contract_success.to_string(),
+                stake_contract_address: contracts.stake_contract.to_string(),
+                reward_denom: "token1".to_string(),
+                claim_id: Some(1),
+                reward_denom_query: None,
+                atomic: false,
+            },
+        );
+        let validate = |app: &App, config: ProtocolConfig| -> ValidateProtocolConfigResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    contracts.autoclaimer.clone(),
+                    &QueryMsg::ValidateProtocolConfig {
+                        config: Box::new(config),
+                    },
+                )
+                .unwrap()
+        };
+
+        assert_eq!(
+            validate(&app, valid_config.clone()),
+            ValidateProtocolConfigResponse {
+                valid: true,
+                errors: vec![],
+            }
+        );
+
+        // Every problem is reported at once
+        let invalid_config = ProtocolConfig {
+            fee_percentage: Decimal::percent(150),
+            fee_address: "".to_string(),
+            strategy: ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+                provider: StakingProvider::DAO_DAO,
+                claim_contract_address: "".to_string(),
+                stake_contract_address: contracts.stake_contract.to_string(),
+                reward_denom: "".to_string(),
+                claim_id: None,
+                reward_denom_query: None,
+                atomic: true,
+            },
+            ..valid_config.clone()
+        };
+        let res = validate(&app, invalid_config.clone());
+        assert!(!res.valid);
+        assert_eq!(res.errors.len(), 6, "{:?}", res.errors);
+        for expected in [
+            "fee_percentage must not exceed 1",
+            "fee_address",
+            "claim_contract_address",
+            "reward_denom must not be empty",
+            "claim_id is required",
+            "atomic requires a pending_rewards_query",
+        ] {
+            assert!(
+                res.errors.iter().any(|error| error.contains(expected)),
+                "missing {expected:?} in {:?}",
+                res.errors
+            );
+        }
+
+        let res = validate(
+            &app,
+            ProtocolConfig {
+                fee_destination: FeeDestination::Stake {
+                    treasury: Addr::unchecked("treasury"),
+                },
+                pending_rewards_query: Some("{not json".to_string()),
+                strategy: ProtocolStrategy::ClaimOnlyFIN {
+                    supported_markets: vec!["".to_string()],
+                },
+                ..valid_config
+            },
+        );
+        assert!(!res.valid);
+        assert_eq!(res.errors.len(), 3, "{:?}", res.errors);
+
+        // The query does not save anything, and UpdateConfig runs the same validation
+        let err = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::UpdateConfig {
+                    config: UpdateConfigMsg {
+                        protocol_configs: Some(vec![invalid_config]),
+                        ..default_update_config()
+                    },
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InvalidProtocolConfig { protocol, .. } if protocol == "protocol3"
+        ));
+        assert!(PROTOCOL_CONFIG
+            .query(&app.wrap(), contracts.autoclaimer, "protocol3")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_min_claim_reward() {
+        // Runs a claim on a protocol whose claim contract reports 1000 pending rewards
+        let claim_with_threshold = |min_claim_reward: u128| {
+            let (mut app, contracts) = setup();
+            let owner = Addr::unchecked("owner");
+            let user = Addr::unchecked("user1");
+            mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+            mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+            app.execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::UpdateConfig {
+                    config: UpdateConfigMsg {
+                        protocol_configs: Some(vec![ProtocolConfig {
+                            pending_rewards_query: Some("{}".to_string()),
+                            min_claim_reward: Uint128::new(min_claim_reward),
+                            ..claim_and_stake_protocol(
+                                "thresholded",
+                                &contracts.claim_contract_success,
+                                &contracts.stake_contract,
+                            )
+                        }]),
+                        ..default_update_config()
+                    },
+                },
+                &[],
+            )
+            .unwrap();
+
+            app.execute_contract(
+                user.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::Subscribe {
+                    protocols: vec!["thresholded".to_string()],
+                },
+                &[],
+            )
+            .unwrap();
+
+            app.execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["thresholded".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap()
+        };
+
+        // Below the threshold the claim is not even dispatched
+        let res = claim_with_threshold(1001);
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_and_stake", "ignored_count"),
+            vec!["1".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_and_stake", "below_min_reward"),
+            vec![format!(
+                "{:?}",
+                vec![(Addr::unchecked("user1"), "thresholded".to_string())]
+            )]
+        );
+        assert!(event_attribute_values(&res, "claim", "result").is_empty());
+
+        // Reaching the threshold the claim goes through
+        let res = claim_with_threshold(1000);
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_and_stake", "ignored_count"),
+            vec!["0".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim", "result"),
+            vec!["ok".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_migrate_clears_legacy_pending_data() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner_info = mock_info("owner", &[]);
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            owner_info.clone(),
+            default_instantiate_msg(),
+        )
+        .unwrap();
+
+        // Entries left by an older deployment under the legacy namespace
+        let legacy_pending = Map::<u64, (Addr, String)>::new("pending_user_protocol");
+        for id in [1000, 1001, 4000] {
+            legacy_pending
+                .save(
+                    deps.as_mut().storage,
+                    id,
+                    &(Addr::unchecked("user1"), "protocol1".to_string()),
+                )
+                .unwrap();
+        }
+
+        // Claim and stake and claim only entries left by an older deployment, both stored as
+        // bare tuples under the claim only namespace
+        let legacy_claim_and_stake =
+            Map::<u64, (Addr, String, Uint128)>::new("pending_claim_only_data");
+        for id in [1000, 1001] {
+            legacy_claim_and_stake
+                .save(
+                    deps.as_mut().storage,
+                    id,
+                    &(
+                        Addr::unchecked("user1"),
+                        "protocol1".to_string(),
+                        Uint128::one(),
+                    ),
+                )
+                .unwrap();
+        }
+        Map::<u64, (String, Addr, Addr)>::new("pending_claim_only_data")
+            .save(
+                deps.as_mut().storage,
+                4000,
+                &(
+                    "FIN".to_string(),
+                    Addr::unchecked("user1"),
+                    Addr::unchecked("fin_market"),
+                ),
+            )
+            .unwrap();
+        PENDING_CLAIM_ONLY_DATA
+            .save(
+                deps.as_mut().storage,
+                4001,
+                &PendingData {
+                    data: (
+                        "FIN".to_string(),
+                        Addr::unchecked("user1"),
+                        Addr::unchecked("fin_market"),
+                        vec![],
+                    ),
+                    saved_at: env.block.time,
+                },
+            )
+            .unwrap();
+        PENDING_FEE_DATA
+            .save(
+                deps.as_mut().storage,
+                3000,
+                &PendingData {
+                    data: (
+                        Addr::unchecked("user1"),
+                        "protocol1".to_string(),
+                        Uint128::one(),
+                    ),
+                    saved_at: env.block.time,
+                },
+            )
+            .unwrap();
+
+        let res = migrate(deps.as_mut(), env, owner_info).unwrap();
+        let attribute = |key: &str| {
+            res.attributes
+                .iter()
+                .find(|attr| attr.key == key)
+                .map(|attr| attr.value.clone())
+        };
+        assert_eq!(attribute("legacy_pending_removed"), Some("3".to_string()));
+        assert_eq!(
+            attribute("undecodable_pending_removed"),
+            Some("3".to_string())
+        );
+
+        assert_eq!(
+            legacy_pending
+                .keys_raw(deps.as_ref().storage, None, None, Order::Ascending)
+                .count(),
+            0
+        );
+        for id in [1000, 1001, 4000] {
+            assert!(!PENDING_CLAIM_ONLY_DATA.has(deps.as_ref().storage, id));
+        }
+        // Current pending data is left alone
+        assert!(PENDING_CLAIM_ONLY_DATA.has(deps.as_ref().storage, 4001));
+        assert!(PENDING_FEE_DATA.has(deps.as_ref().storage, 3000));
+    }
+
+    #[test]
+    fn test_claim_fully_consumed_by_fee() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![ProtocolConfig {
+                        fee_percentage: Decimal::one(),
+                        ..claim_and_stake_protocol(
+                            "full_fee",
+                            &contracts.claim_contract_success,
+                            &contracts.stake_contract,
+                        )
+                    }]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["full_fee".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["full_fee".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        // The fee takes the whole claim and is still sent
+        assert_eq!(
+            event_attribute_values(&res, "claim", "result"),
+            vec!["ok".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "fully_consumed_by_fee", "fee"),
+            vec!["1000".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "charge_fee", "result"),
+            vec!["ok".to_string()]
+        );
+        let fee_balance = app.wrap().query_balance("feeaddress1", "token1").unwrap();
+        assert_eq!(fee_balance.amount, Uint128::new(1000));
+
+        // No zero stake is attempted
+        assert!(event_attribute_values(&res, "claim", "tokens_to_stake").is_empty());
+        assert!(event_attribute_values(&res, "stake", "result").is_empty());
+    }
+
+    #[test]
+    fn test_direct_claim() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![ProtocolConfig {
+                        direct_claim: true,
+                        ..claim_and_stake_protocol(
+                            "direct",
+                            &contracts.claim_contract_success,
+                            &contracts.stake_contract,
+                        )
+                    }]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+        // The contract holds a position of its own, next to a regular user
+        for subscriber in [&user, &contracts.autoclaimer] {
+            app.execute_contract(
+                subscriber.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::Subscribe {
+                    protocols: vec!["direct".to_string()],
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![
+                        (user.to_string(), vec!["direct".to_string()]),
+                        (
+                            contracts.autoclaimer.to_string(),
+                            vec!["direct".to_string()],
+                        ),
+                    ],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        // Only the position of the contract can be claimed directly
+        let data: ClaimAndStakeResponseData = from_json(res.data.clone().unwrap()).unwrap();
+        assert_eq!(data.dispatched, 1);
+        assert_eq!(data.ignored, vec![(user.to_string(), "direct".to_string())]);
+
+        // The claim contract is executed by the contract itself, which receives the rewards
+        assert!(res.events.iter().any(|event| {
+            event.ty == "execute"
+                && event.attributes.iter().any(|attr| {
+                    attr.key == "_contract_address"
+                        && attr.value == contracts.claim_contract_success.as_str()
+                })
+        }));
+        assert_eq!(
+            event_attribute_values(&res, "claim", "address"),
+            vec![contracts.autoclaimer.to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim", "tokens_claimed"),
+            vec!["1000".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_protocol_count() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+
+        let protocol_count = |app: &App| -> u32 {
+            let res: ProtocolCountResponse = app
+                .wrap()
+                .query_wasm_smart(contracts.autoclaimer.clone(), &QueryMsg::ProtocolCount {})
+                .unwrap();
+            res.count
+        };
+        let update_protocols = |app: &mut App, protocols: Vec<&str>| {
+            let protocol_configs = protocols
+                .into_iter()
+                .map(|protocol| {
+                    protocol_config(
+                        protocol,
+                        ProtocolStrategy::ClaimOnlyFIN {
+                            supported_markets: vec![],
+                        },
+                    )
+                })
+                .collect();
+            app.execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::UpdateConfig {
+                    config: UpdateConfigMsg {
+                        protocol_configs: Some(protocol_configs),
+                        ..default_update_config()
+                    },
+                },
+                &[],
+            )
+            .unwrap();
+        };
+
+        // protocol1, protocol2 and FIN
+        assert_eq!(protocol_count(&app), 3);
+
+        // Only new protocols are counted, updates of existing ones are not
+        update_protocols(&mut app, vec!["protocol3", "FIN"]);
+        assert_eq!(protocol_count(&app), 4);
+
+        let err = app
+            .execute_contract(
+                Addr::unchecked("user1"),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::DeleteProtocol {
+                    protocol: "protocol3".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Unauthorized
+        ));
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::DeleteProtocol {
+                protocol: "protocol3".to_string(),
+                start_after: None,
+                limit: None,
+            },
+            &[],
+        )
+        .unwrap();
+        assert_eq!(protocol_count(&app), 3);
+        assert!(PROTOCOL_CONFIG
+            .query(&app.wrap(), contracts.autoclaimer.clone(), "protocol3")
+            .unwrap()
+            .is_none());
+
+        // Deleting an unknown protocol leaves the count alone
+        let err = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::DeleteProtocol {
+                    protocol: "protocol3".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InvalidProtocol { .. }
+        ));
+        assert_eq!(protocol_count(&app), 3);
+    }
+
+    #[test]
+    fn test_replies_consume_pending_data() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner_info = mock_info("owner", &[]);
+        deps.querier.update_wasm(|_| {
+            SystemResult::Ok(ContractResult::Ok(
+                to_json_binary(&FinConfigData {
+                    denoms: vec!["token1".to_string(), "usdc".to_string()],
+                })
+                .unwrap(),
+            ))
+        });
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            owner_info.clone(),
+            InstantiateMsg {
+                protocol_configs: vec![
+                    claim_and_stake_protocol(
+                        "protocol1",
+                        &Addr::unchecked("claim_contract"),
+                        &Addr::unchecked("stake_contract"),
+                    ),
+                    ProtocolConfig {
+                        fee_percentage: Decimal::zero(),
+                        fee_address: "".to_string(),
+                        ..protocol_config(
+                            "FIN",
+                            ProtocolStrategy::ClaimOnlyFIN {
+                                supported_markets: vec!["fin_market".to_string()],
+                            },
+                        )
+                    },
+                ],
+                ..default_instantiate_msg()
+            },
+        )
+        .unwrap();
+
+        for user in ["user1", "user2"] {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(user, &[]),
+                ExecuteMsg::Subscribe {
+                    protocols: vec!["protocol1".to_string(), "FIN".to_string()],
+                },
+            )
+            .unwrap();
+        }
+
+        // Two sequential batches, each dispatching claims that are then replied to
+        for _ in 0..2 {
+            let claim_and_stake = execute(
+                deps.as_mut(),
+                env.clone(),
+                owner_info.clone(),
+                ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![
+                        ("user1".to_string(), vec!["protocol1".to_string()]),
+                        ("user2".to_string(), vec!["protocol1".to_string()]),
+                    ],
+                    deadline: None,
+                },
+            )
+            .unwrap();
+            let claim_only = execute(
+                deps.as_mut(),
+                env.clone(),
+                owner_info.clone(),
+                ExecuteMsg::ClaimOnly {
+                    protocol: "FIN".to_string(),
+                    users_contracts: vec![
+                        ("user1".to_string(), "fin_market".to_string()),
+                        ("user2".to_string(), "fin_market".to_string()),
+                    ],
+                    deadline: None,
+                },
+            )
+            .unwrap();
+
+            let submsgs: Vec<SubMsg> = claim_and_stake
+                .messages
+                .into_iter()
+                .chain(claim_only.messages)
+                .collect();
+            assert_eq!(submsgs.len(), 4);
+            for (index, submsg) in submsgs.into_iter().enumerate() {
+                // Replies consume their data on success and on failure alike
+                let result = if index % 2 == 0 {
+                    SubMsgResult::Ok(SubMsgResponse {
+                        events: vec![],
+                        data: None,
+                    })
+                } else {
+                    SubMsgResult::Err("claim failed".to_string())
+                };
+                reply(
+                    deps.as_mut(),
+                    env.clone(),
+                    Reply {
+                        id: submsg.id,
+                        result,
+                    },
+                )
+                .unwrap();
+            }
+        }
+
+        assert_eq!(
+            PENDING_CLAIM_AND_STAKE_DATA
+                .range(deps.as_ref().storage, None, None, Order::Ascending)
+                .count(),
+            0
+        );
+        assert_eq!(
+            PENDING_CLAIM_ONLY_DATA
+                .range(deps.as_ref().storage, None, None, Order::Ascending)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_offboard() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let users = [Addr::unchecked("user1"), Addr::unchecked("user2")];
+        mint(&mut app, &contracts.claim_contract_success, "token1", 2000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 2000);
+
+        for user in &users {
+            app.execute_contract(
+                user.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::Subscribe {
+                    protocols: vec!["protocol1".to_string(), "FIN".to_string()],
+                },
+                &[],
+            )
+            .unwrap();
+        }
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::ClaimAndStake {
+                users_protocols: users
+                    .iter()
+                    .map(|user| (user.to_string(), vec!["protocol1".to_string()]))
+                    .collect(),
+                deadline: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        // Only the owner can offboard someone else
+        let err = app
+            .execute_contract(
+                users[0].clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::OffboardUser {
+                    user: users[1].to_string(),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Unauthorized
+        ));
+
+        app.execute_contract(
+            users[0].clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Offboard {},
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            owner,
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::OffboardUser {
+                user: users[1].to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+        for user in &users {
+            assert!(SUBSCRIPTIONS
+                .query(&app.wrap(), contracts.autoclaimer.clone(), user)
+                .unwrap()
+                .is_none());
+            for protocol in ["protocol1", "FIN"] {
+                assert!(USER_EXECUTION_DATA
+                    .query(
+                        &app.wrap(),
+                        contracts.autoclaimer.clone(),
+                        (user.clone(), protocol.to_string()),
+                    )
+                    .unwrap()
+                    .is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn test_query_malformed_address() {
+        let (app, contracts) = setup();
+
+        let err = app
+            .wrap()
+            .query_wasm_smart::<GetSubscribedProtocolsResponse>(
+                contracts.autoclaimer.clone(),
+                &QueryMsg::GetSubscribedProtocols {
+                    user_address: "User1".to_string(),
+                },
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid user_address \"User1\""));
+    }
+
+    #[test]
+    fn test_revalidate_configs() {
+        let mut deps = mock_dependencies();
+        let owner_info = mock_info("owner", &[]);
+
+        let valid_config = protocol_config(
+            "protocol1",
+            ProtocolStrategy::ClaimOnlyFIN {
+                supported_markets: vec![],
+            },
+        );
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            InstantiateMsg {
+                protocol_configs: vec![valid_config.clone()],
+                ..default_instantiate_msg()
+            },
+        )
+        .unwrap();
+
+        // A config stored before validation existed
+        let stale_config = ProtocolConfig {
+            protocol: "protocol2".to_string(),
+            fee_percentage: Decimal::percent(150),
+            ..valid_config
+        };
+        PROTOCOL_CONFIG
+            .save(deps.as_mut().storage, "protocol2", &stale_config)
+            .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("user1", &[]),
+            ExecuteMsg::RevalidateConfigs {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            owner_info,
+            ExecuteMsg::RevalidateConfigs {},
+        )
+        .unwrap();
+        let attribute = |action: &str, key: &str| -> Vec<String> {
+            res.events
+                .iter()
+                .filter(|event| {
+                    event
+                        .attributes
+                        .iter()
+                        .any(|attr| attr.key == "action" && attr.value == action)
+                })
+                .flat_map(|event| event.attributes.iter().filter(|attr| attr.key == key))
+                .map(|attr| attr.value.clone())
+                .collect()
+        };
+        assert_eq!(
+            attribute("revalidate_configs", "checked_count"),
+            vec!["2".to_string()]
+        );
+        assert_eq!(
+            attribute("revalidate_configs", "invalid_count"),
+            vec!["1".to_string()]
+        );
+        assert_eq!(
+            attribute("invalid_protocol_config", "protocol"),
+            vec!["protocol2".to_string()]
+        );
+        assert!(attribute("invalid_protocol_config", "errors")[0].contains("fee_percentage"));
+
+        // The invalid config is only reported, not removed
+        assert!(PROTOCOL_CONFIG.has(deps.as_ref().storage, "protocol2"));
+    }
+
+    #[test]
+    fn test_get_subscriptions_pagination() {
+        let (mut app, contracts) = setup();
+
+        let users: Vec<String> = (0..50).map(|i| format!("user{i:02}")).collect();
+        for user in &users {
+            app.execute_contract(
+                Addr::unchecked(user),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::Subscribe {
+                    protocols: vec!["protocol1".to_string()],
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        let page = |app: &App, start_after: Option<String>, limit: Option<u32>| -> Vec<String> {
+            let res: GetSubscriptionsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contracts.autoclaimer.clone(),
+                    &QueryMsg::GetSubscriptions { start_after, limit },
+                )
+                .unwrap();
+            res.subscriptions
+                .into_iter()
+                .map(|(user, _)| user)
+                .collect()
+        };
+
+        // The default page size is used without a limit, and the limit is capped
+        assert_eq!(page(&app, None, None), users[..10].to_vec());
+        assert_eq!(page(&app, None, Some(100)), users[..30].to_vec());
+
+        let mut paged = vec![];
+        let mut start_after = None;
+        loop {
+            let addresses = page(&app, start_after, Some(30));
+            if addresses.is_empty() {
+                break;
+            }
+            start_after = addresses.last().cloned();
+            paged.extend(addresses);
+        }
+        assert_eq!(paged, users);
+    }
+
+    #[test]
+    fn test_claim_window() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+        // An hourly window opening one minute from now and lasting one minute
+        let offset = app.block_info().time.seconds() % 3600;
+        let windowed_protocol = ProtocolConfig {
+            claim_window: Some(ClaimWindow {
+                period: 3600,
+                start: (offset + 60) % 3600,
+                end: (offset + 120) % 3600,
+            }),
+            ..claim_and_stake_protocol(
+                "windowed",
+                &contracts.claim_contract_success,
+                &contracts.stake_contract,
+            )
+        };
+
+        // Empty windows are rejected
+        let res: ValidateProtocolConfigResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contracts.autoclaimer.clone(),
+                &QueryMsg::ValidateProtocolConfig {
+                    config: Box::new(ProtocolConfig {
+                        claim_window: Some(ClaimWindow {
+                            period: 3600,
+                            start: 60,
+                            end: 60,
+                        }),
+                        ..windowed_protocol.clone()
+                    }),
+                },
+            )
+            .unwrap();
+        assert!(!res.valid);
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![windowed_protocol]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["windowed".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let claim = |app: &mut App| {
+            app.execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["windowed".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap()
+        };
+
+        // Before the window opens the claim is not dispatched
+        let res = claim(&mut app);
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_and_stake", "ignored_count"),
+            vec!["1".to_string()]
+        );
+        assert!(event_attribute_values(&res, "claim", "result").is_empty());
+
+        // Inside the window it goes through
+        app.update_block(|block| block.time = block.time.plus_seconds(60));
+        let res = claim(&mut app);
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_and_stake", "ignored_count"),
+            vec!["0".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim", "result"),
+            vec!["ok".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_claim_and_send() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        let send_protocol = protocol_config(
+            "protocol_send",
+            ProtocolStrategy::ClaimAndSend {
+                provider: StakingProvider::CW_REWARDS,
+                claim_contract_address: contracts.claim_contract_success.to_string(),
+                reward_denom: "token1".to_string(),
+                claim_id: None,
+                reward_denom_query: None,
+            },
+        );
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![send_protocol]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol_send".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["protocol_send".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        // 1000 claimed to the user, the 1% fee is charged and the remaining 990 stays with the user
+        assert_eq!(
+            event_attribute_values(&res, "claim", "tokens_to_user"),
+            vec!["990".to_string()]
+        );
+        assert!(event_attribute_values(&res, "stake", "result").is_empty());
+        assert!(event_attribute_values(&res, "swap", "result").is_empty());
+        assert_eq!(
+            event_attribute_values(&res, "charge_fee", "result"),
+            vec!["ok".to_string()]
+        );
+
+        // The mocked fee send pays from the contract balance, so the user keeps the full claim here
+        let fee = app.wrap().query_balance("feeaddress1", "token1").unwrap();
+        assert_eq!(fee.amount, Uint128::new(10));
+        let rewards = app.wrap().query_balance(&user, "token1").unwrap();
+        assert_eq!(rewards.amount, Uint128::new(1000));
+    }
+
+    #[test]
+    fn test_claim_only_reports_claimed_tokens() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        // Filled orders paid out in both market denoms
+        mint(&mut app, &contracts.fin_contract_addr, "token1", 300);
+        mint(&mut app, &contracts.fin_contract_addr, "usdc", 700);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["FIN".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let claim_only = |app: &mut App| {
+            app.execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimOnly {
+                    protocol: "FIN".to_string(),
+                    users_contracts: vec![(
+                        user.to_string(),
+                        contracts.fin_contract_addr.to_string(),
+                    )],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap()
+        };
+
+        let res = claim_only(&mut app);
+        assert_eq!(
+            event_attribute_values(&res, "claim", "token"),
+            vec!["token1".to_string(), "usdc".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim", "tokens_claimed"),
+            vec!["300".to_string(), "700".to_string()]
+        );
+
+        // Denoms that did not change are not reported
+        mint(&mut app, &contracts.fin_contract_addr, "usdc", 50);
+        let res = claim_only(&mut app);
+        assert_eq!(
+            event_attribute_values(&res, "claim", "token"),
+            vec!["usdc".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim", "tokens_claimed"),
+            vec!["50".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_min_claim_amount() {
+        // Claims 1000 token1 on a protocol skipping claimed amounts below the threshold
+        let claim_with_threshold = |min_claim_amount: u128| {
+            let (mut app, contracts) = setup();
+            let owner = Addr::unchecked("owner");
+            let user = Addr::unchecked("user1");
+            mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+            mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+            app.execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::UpdateConfig {
+                    config: UpdateConfigMsg {
+                        protocol_configs: Some(vec![ProtocolConfig {
+                            min_claim_amount: Some(Uint128::new(min_claim_amount)),
+                            ..claim_and_stake_protocol(
+                                "dusty",
+                                &contracts.claim_contract_success,
+                                &contracts.stake_contract,
+                            )
+                        }]),
+                        ..default_update_config()
+                    },
+                },
+                &[],
+            )
+            .unwrap();
+
+            app.execute_contract(
+                user.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::Subscribe {
+                    protocols: vec!["dusty".to_string()],
+                },
+                &[],
+            )
+            .unwrap();
+
+            let res = app
+                .execute_contract(
+                    owner,
+                    contracts.autoclaimer.clone(),
+                    &ExecuteMsg::ClaimAndStake {
+                        users_protocols: vec![(user.to_string(), vec!["dusty".to_string()])],
+                        deadline: None,
+                    },
+                    &[],
+                )
+                .unwrap();
+            let execution_data = USER_EXECUTION_DATA
+                .query(
+                    &app.wrap(),
+                    contracts.autoclaimer.clone(),
+                    (user, "dusty".to_string()),
+                )
+                .unwrap();
+            (res, execution_data)
+        };
+
+        // Below the threshold nothing is staked or charged, and the cooldown does not start
+        let (res, execution_data) = claim_with_threshold(1001);
+        assert_eq!(
+            event_attribute_values(&res, "claim", "result"),
+            vec!["skipped_dust".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim", "tokens_claimed"),
+            vec!["1000".to_string()]
+        );
+        assert!(event_attribute_values(&res, "stake", "result").is_empty());
+        assert!(event_attribute_values(&res, "charge_fee", "result").is_empty());
+        assert!(execution_data.is_none());
+
+        // Reaching the threshold the rewards are staked
+        let (res, execution_data) = claim_with_threshold(1000);
+        assert_eq!(
+            event_attribute_values(&res, "claim", "result"),
+            vec!["ok".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "stake", "result"),
+            vec!["ok".to_string()]
+        );
+        assert!(execution_data.unwrap().last_autoclaim.is_some());
+    }
+
+    #[test]
+    fn test_quote_batch_fees() {
+        let (app, contracts) = setup();
+
+        let items = vec![
+            ("protocol1".to_string(), Uint128::new(1000)),
+            ("FIN".to_string(), Uint128::new(500)),
+            ("protocol2".to_string(), Uint128::new(250)),
+            ("protocol1".to_string(), Uint128::new(550)),
+        ];
+        let res: QuoteBatchFeesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contracts.autoclaimer.clone(),
+                &QueryMsg::QuoteBatchFees {
+                    items: items.clone(),
+                },
+            )
+            .unwrap();
+
+        // Each item quoted on its own with the effective fee of its protocol
+        let item_fees: Vec<Uint128> = items
+            .iter()
+            .map(|(protocol, amount)| {
+                let effective_fee: EffectiveFeeResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        contracts.autoclaimer.clone(),
+                        &QueryMsg::EffectiveFee {
+                            user_address: "user1".to_string(),
+                            protocol: protocol.clone(),
+                        },
+                    )
+                    .unwrap();
+                if effective_fee.exempt {
+                    Uint128::zero()
+                } else {
+                    *amount * effective_fee.percentage
+                }
+            })
+            .collect();
+
+        assert_eq!(
+            res.fees,
+            vec![
+                ("protocol1".to_string(), item_fees[0] + item_fees[3]),
+                ("FIN".to_string(), item_fees[1]),
+                ("protocol2".to_string(), item_fees[2]),
+            ]
+        );
+        assert_eq!(res.total, item_fees.iter().copied().sum::<Uint128>());
+        assert_eq!(res.total, Uint128::new(10 + 2 + 5));
+
+        // Unknown protocols are rejected
+        assert!(app
+            .wrap()
+            .query_wasm_smart::<QuoteBatchFeesResponse>(
+                contracts.autoclaimer.clone(),
+                &QueryMsg::QuoteBatchFees {
+                    items: vec![("unknown".to_string(), Uint128::new(1000))],
+                },
+            )
+            .is_err());
+
+        // Fees overflowing the total fail the query instead of panicking
+        let err = app
+            .wrap()
+            .query_wasm_smart::<QuoteBatchFeesResponse>(
+                contracts.autoclaimer.clone(),
+                &QueryMsg::QuoteBatchFees {
+                    items: vec![("protocol1".to_string(), Uint128::MAX); 200],
+                },
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("Overflow"), "{err}");
+    }
+
+    #[test]
+    fn test_two_step_ownership_transfer() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let new_owner = Addr::unchecked("newowner");
+        let config = |app: &App| -> ConfigResponse {
+            app.wrap()
+                .query_wasm_smart(contracts.autoclaimer.clone(), &QueryMsg::Config {})
+                .unwrap()
+        };
+
+        // Only the owner can propose
+        let err = app
+            .execute_contract(
+                new_owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ProposeNewOwner {
+                    new_owner: new_owner.to_string(),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Unauthorized
+        ));
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::ProposeNewOwner {
+                new_owner: new_owner.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+        let res = config(&app);
+        assert_eq!(res.owner, owner);
+        assert_eq!(res.pending_owner, Some(new_owner.clone()));
+
+        // Nobody but the proposed owner can accept
+        for sender in [owner.clone(), Addr::unchecked("user1")] {
+            let err = app
+                .execute_contract(
+                    sender,
+                    contracts.autoclaimer.clone(),
+                    &ExecuteMsg::AcceptOwnership {},
+                    &[],
+                )
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::Unauthorized
+            ));
+        }
+
+        app.execute_contract(
+            new_owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::AcceptOwnership {},
+            &[],
+        )
+        .unwrap();
+        let res = config(&app);
+        assert_eq!(res.owner, new_owner);
+        assert_eq!(res.pending_owner, None);
+
+        // The previous owner lost its rights
+        let err = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ProposeNewOwner {
+                    new_owner: "user1".to_string(),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::Unauthorized
+        ));
+    }
+
+    #[test]
+    fn test_get_protocol_config() {
+        let (app, contracts) = setup();
+
+        let res: ProtocolConfigResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contracts.autoclaimer.clone(),
+                &QueryMsg::GetProtocolConfig {
+                    protocol: "protocol1".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.config.protocol, "protocol1");
+        assert_eq!(
+            res.config.strategy,
+            ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+                provider: StakingProvider::CW_REWARDS,
+                claim_contract_address: contracts.claim_contract_success.to_string(),
+                stake_contract_address: contracts.stake_contract.to_string(),
+                reward_denom: "token1".to_string(),
+                claim_id: None,
+                reward_denom_query: None,
+                atomic: false,
+            }
+        );
+
+        let err = app
+            .wrap()
+            .query_wasm_smart::<ProtocolConfigResponse>(
+                contracts.autoclaimer.clone(),
+                &QueryMsg::GetProtocolConfig {
+                    protocol: "unknown".to_string(),
+                },
+            )
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("protocol config of unknown not found"));
+    }
+
+    #[test]
+    fn test_delete_protocol_prunes_subscriptions() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+
+        for (user, protocols) in [
+            ("user1", vec!["protocol1"]),
+            ("user2", vec!["protocol1", "protocol2"]),
+            ("user3", vec!["protocol2", "protocol1"]),
+        ] {
+            app.execute_contract(
+                Addr::unchecked(user),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::Subscribe {
+                    protocols: protocols.into_iter().map(String::from).collect(),
+                },
+                &[],
+            )
+            .unwrap();
+        }
+        let protocol1: ProtocolConfigResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contracts.autoclaimer.clone(),
+                &QueryMsg::GetProtocolConfig {
+                    protocol: "protocol1".to_string(),
+                },
+            )
+            .unwrap();
+
+        // The first page removes the config and prunes user1 and user2
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::DeleteProtocol {
+                    protocol: "protocol1".to_string(),
+                    start_after: None,
+                    limit: Some(2),
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            event_attribute_values(&res, "delete_protocol", "affected_subscribers"),
+            vec!["2".to_string()]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "delete_protocol", "next_start_after"),
+            vec!["user2".to_string()]
+        );
+
+        let subscriptions = |app: &App, user: &str| -> Option<Vec<String>> {
+            SUBSCRIPTIONS
+                .query(
+                    &app.wrap(),
+                    contracts.autoclaimer.clone(),
+                    &Addr::unchecked(user),
+                )
+                .unwrap()
+        };
+        assert_eq!(subscriptions(&app, "user1"), None);
+        assert_eq!(
+            subscriptions(&app, "user2"),
+            Some(vec!["protocol2".to_string()])
+        );
+
+        // Until its page is pruned, claims skip the deleted protocol instead of failing the batch
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![("user3".to_string(), vec!["protocol1".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_and_stake", "ignored_count"),
+            vec!["1".to_string()]
+        );
+
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::DeleteProtocol {
+                    protocol: "protocol1".to_string(),
+                    start_after: Some("user2".to_string()),
+                    limit: Some(2),
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            event_attribute_values(&res, "delete_protocol", "affected_subscribers"),
+            vec!["1".to_string()]
+        );
+        assert!(event_attribute_values(&res, "delete_protocol", "next_start_after").is_empty());
+        assert_eq!(
+            subscriptions(&app, "user3"),
+            Some(vec!["protocol2".to_string()])
+        );
+
+        // The deleted protocol can not be subscribed to anymore
+        let err = app
+            .execute_contract(
+                Addr::unchecked("user1"),
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::Subscribe {
+                    protocols: vec!["protocol1".to_string()],
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InvalidProtocol { .. }
+        ));
+
+        // Adding it back does not bring the old subscriptions back
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![protocol1.config]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+        for user in ["user1", "user2", "user3"] {
+            let res: IsSubscribedResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contracts.autoclaimer.clone(),
+                    &QueryMsg::IsSubscribed {
+                        user_address: user.to_string(),
+                        protocol: "protocol1".to_string(),
+                    },
+                )
+                .unwrap();
+            assert!(!res.subscribed, "{user}");
+        }
+
+        // A stale cursor can not prune the protocol once it is configured again
+        app.execute_contract(
+            owner,
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::DeleteProtocol {
+                protocol: "protocol1".to_string(),
+                start_after: Some("user2".to_string()),
+                limit: None,
+            },
+            &[],
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn test_migrate_backfills_dao_dao_claim_id() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner_info = mock_info("owner", &[]);
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            owner_info.clone(),
+            default_instantiate_msg(),
+        )
+        .unwrap();
+
+        // Strategy format config stored before the claim ID was configurable
+        let stored = r#"{"protocol":"protocol1","fee_percentage":"0.1","fee_address":"fee_address","strategy":{"type":"ClaimAndStakeDaoDaoCwRewards","provider":"DAO_DAO","claim_contract_address":"claim","stake_contract_address":"stake","reward_denom":"token1"}}"#;
+        deps.as_mut()
+            .storage
+            .set(&PROTOCOL_CONFIG.key("protocol1"), stored.as_bytes());
+
+        migrate(deps.as_mut(), env.clone(), owner_info).unwrap();
+
+        // The count is seeded from the stored protocols, not only from legacy ones
+        let res: ProtocolCountResponse =
+            from_json(query(deps.as_ref(), env, QueryMsg::ProtocolCount {}).unwrap()).unwrap();
+        assert_eq!(res.count, 1);
+
+        let protocol_config = PROTOCOL_CONFIG
+            .load(deps.as_ref().storage, "protocol1")
+            .unwrap();
+        match protocol_config.strategy {
+            ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards { claim_id, .. } => {
+                assert_eq!(claim_id, Some(2))
+            }
+            _ => panic!("unexpected strategy"),
+        }
+    }
+
+    #[test]
+    fn test_min_claim_reward_preview_failure_skips_pair() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+
+        // The stake contract answers the pending rewards query with an unparsable response
+        let thresholded_config = |protocol: &str, claim_contract: &Addr| ProtocolConfig {
+            pending_rewards_query: Some("{}".to_string()),
+            min_claim_reward: Uint128::one(),
+            ..claim_and_stake_protocol(protocol, claim_contract, &contracts.stake_contract)
+        };
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![
+                        thresholded_config("previewable", &contracts.claim_contract_success),
+                        thresholded_config("unpreviewable", &contracts.stake_contract),
+                    ]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["previewable".to_string(), "unpreviewable".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(
+                        user.to_string(),
+                        vec!["previewable".to_string(), "unpreviewable".to_string()],
+                    )],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_and_stake", "preview_failed"),
+            vec![format!(
+                "{:?}",
+                vec![(Addr::unchecked("user1"), "unpreviewable".to_string())]
+            )]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim", "result"),
+            vec!["ok".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_atomic_preview_failure_skips_pair() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        let code_id = app.store_code(mock_atomic_claim_contract());
+        let atomic_claim_contract = app
+            .instantiate_contract(
+                code_id,
+                owner.clone(),
+                &Empty {},
+                &[],
+                "Mock Atomic Claim Contract",
+                None,
+            )
+            .unwrap();
+
+        // The stake contract answers the pending rewards query with an unparsable response
+        let atomic_config = |protocol: &str, claim_contract: &Addr| ProtocolConfig {
+            pending_rewards_query: Some("{}".to_string()),
+            ..protocol_config(
+                protocol,
+                ProtocolStrategy::ClaimAndStakeDaoDaoCwRewards {
+                    provider: StakingProvider::CW_REWARDS,
+                    claim_contract_address: claim_contract.to_string(),
+                    stake_contract_address: contracts.stake_contract.to_string(),
+                    reward_denom: "token1".to_string(),
+                    claim_id: None,
+                    reward_denom_query: None,
+                    atomic: true,
+                },
+            )
+        };
+
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![
+                        atomic_config("atomic", &atomic_claim_contract),
+                        atomic_config("unpreviewable", &contracts.stake_contract),
+                    ]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        mint(&mut app, &atomic_claim_contract, "token1", 1000);
+        // Mocked sends come out of the contract balance: fee send
+        mint(&mut app, &contracts.autoclaimer, "token1", 10);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["atomic".to_string(), "unpreviewable".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(
+                        user.to_string(),
+                        vec!["atomic".to_string(), "unpreviewable".to_string()],
+                    )],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_and_stake", "preview_failed"),
+            vec![format!(
+                "{:?}",
+                vec![(Addr::unchecked("user1"), "unpreviewable".to_string())]
+            )]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim_and_stake", "result"),
+            vec!["ok".to_string()]
+        );
+        let staked = app
+            .wrap()
+            .query_balance(contracts.stake_contract.to_string(), "token1")
+            .unwrap();
+        assert_eq!(staked.amount, Uint128::new(990));
+    }
+
+    #[test]
+    fn test_claim_only_snapshot_failure_skips_market() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        mint(&mut app, &contracts.fin_contract_addr, "token1", 300);
+
+        // The stake contract does not answer the FIN config query
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::UpdateConfig {
+                config: UpdateConfigMsg {
+                    protocol_configs: Some(vec![ProtocolConfig {
+                        fee_percentage: Decimal::zero(),
+                        fee_address: "".to_string(),
+                        ..protocol_config(
+                            "FIN",
+                            ProtocolStrategy::ClaimOnlyFIN {
+                                supported_markets: vec![
+                                    contracts.fin_contract_addr.to_string(),
+                                    contracts.stake_contract.to_string(),
+                                ],
+                            },
+                        )
+                    }]),
+                    ..default_update_config()
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["FIN".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimOnly {
+                    protocol: "FIN".to_string(),
+                    users_contracts: vec![
+                        (user.to_string(), contracts.stake_contract.to_string()),
+                        (user.to_string(), contracts.fin_contract_addr.to_string()),
+                    ],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        assert_eq!(
+            event_attribute_values(&res, "execute_claim_only", "snapshot_failed"),
+            vec![format!(
+                "{:?}",
+                vec![(user.to_string(), contracts.stake_contract.to_string())]
+            )]
+        );
+        assert_eq!(
+            event_attribute_values(&res, "claim", "tokens_claimed"),
+            vec!["300".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_claim_and_swap_fin_min_price() {
+        let (mut app, contracts) = setup();
+        let owner = Addr::unchecked("owner");
+        let user = Addr::unchecked("user1");
+
+        let swap_protocol = protocol_config(
+            "protocol_swap",
+            ProtocolStrategy::ClaimAndSwapFIN {
+                provider: StakingProvider::CW_REWARDS,
+                claim_contract_address: contracts.claim_contract_success.to_string(),
+                reward_denom: "token1".to_string(),
+                fin_market: contracts.fin_contract_addr.to_string(),
+                target_denom: "uatom".to_string(),
+                min_price: Some(Decimal::percent(150)),
+                claim_id: None,
+                reward_denom_query: None,
+            },
+        );
+        let update_config = |config: ProtocolConfig| ExecuteMsg::UpdateConfig {
+            config: UpdateConfigMsg {
+                protocol_configs: Some(vec![config]),
+                ..default_update_config()
+            },
+        };
+
+        let validation_errors = |app: &App, reward_denom: &str, target_denom: &str| {
+            let mut config = swap_protocol.clone();
+            if let ProtocolStrategy::ClaimAndSwapFIN {
+                reward_denom: config_reward_denom,
+                target_denom: config_target_denom,
+                ..
+            } = &mut config.strategy
+            {
+                *config_reward_denom = reward_denom.to_string();
+                *config_target_denom = target_denom.to_string();
+            }
+            let res: ValidateProtocolConfigResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contracts.autoclaimer.clone(),
+                    &QueryMsg::ValidateProtocolConfig {
+                        config: Box::new(config),
+                    },
+                )
+                .unwrap();
+            res.errors
+        };
+
+        // The market only trades token1 and usdc
+        assert_eq!(
+            validation_errors(&app, "token1", "uatom"),
+            vec!["fin_market does not trade target_denom uatom".to_string()]
+        );
+        assert_eq!(
+            validation_errors(&app, "token2", "usdc"),
+            vec!["fin_market does not trade reward_denom token2".to_string()]
+        );
+        assert_eq!(
+            validation_errors(&app, "usdc", "usdc"),
+            vec!["target_denom must differ from reward_denom".to_string()]
+        );
+        assert!(validation_errors(&app, "token1", "usdc").is_empty());
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &update_config(swap_protocol.clone()),
+            &[],
+        )
+        .unwrap_err();
+
+        let mut swap_protocol = swap_protocol;
+        if let ProtocolStrategy::ClaimAndSwapFIN { target_denom, .. } = &mut swap_protocol.strategy
+        {
+            *target_denom = "usdc".to_string();
+        }
+        app.execute_contract(
+            owner.clone(),
+            contracts.autoclaimer.clone(),
+            &update_config(swap_protocol),
+            &[],
+        )
+        .unwrap();
+
+        mint(&mut app, &contracts.claim_contract_success, "token1", 1000);
+        mint(&mut app, &contracts.autoclaimer, "token1", 1000);
+        mint(&mut app, &contracts.fin_contract_addr, "usdc", 1000);
+
+        app.execute_contract(
+            user.clone(),
+            contracts.autoclaimer.clone(),
+            &ExecuteMsg::Subscribe {
+                protocols: vec!["protocol_swap".to_string()],
+            },
+            &[],
+        )
+        .unwrap();
+
+        let res = app
+            .execute_contract(
+                owner,
+                contracts.autoclaimer.clone(),
+                &ExecuteMsg::ClaimAndStake {
+                    users_protocols: vec![(user.to_string(), vec!["protocol_swap".to_string()])],
+                    deadline: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        // The 1:1 market returns 990 usdc, below the 1485 asked for
+        assert_eq!(
+            event_attribute_values(&res, "swap", "result"),
+            vec!["failed".to_string()]
+        );
+        let usdc = app.wrap().query_balance(&user, "usdc").unwrap();
+        assert_eq!(usdc.amount, Uint128::zero());
+    }
 }
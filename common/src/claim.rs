@@ -2,7 +2,7 @@ use crate::{
     common_functions::{build_authz_msg, AuthzMessageType},
     staking_provider::StakingProvider,
 };
-use cosmwasm_std::{Addr, CosmosMsg, Env, StdResult};
+use cosmwasm_std::{Addr, CosmosMsg, Env, StdResult, WasmMsg};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -31,7 +31,7 @@ pub struct ClaimParamsCwRewards {}
 /// * `user` - The address of the user who will claim the rewards.
 /// * `provider` - The claim provider (DAO_DAO, CW_REWARDS).
 /// * `claim_contract_address` - The address of the claim contract.
-/// * `claim_id` - The ID of the claim.
+/// * `claim_id` - The ID of the claim. Required by DAO_DAO, ignored by CW_REWARDS.
 ///
 /// # Returns
 ///
@@ -41,18 +41,78 @@ pub fn build_claim_msg(
     user: Addr,
     provider: StakingProvider,
     claim_contract_address: Addr,
-    claim_id: u64,
+    claim_id: Option<u64>,
 ) -> StdResult<CosmosMsg> {
+    build_authz_msg(
+        env,
+        user,
+        claim_msg_type(provider, claim_contract_address, claim_id)?,
+    )
+}
+
+/// Constructs the claim message of a provider, to be wrapped in an Authz message.
+///
+/// # Arguments
+///
+/// * `provider` - The claim provider (DAO_DAO, CW_REWARDS, CW20_STAKE).
+/// * `claim_contract_address` - The address of the claim contract.
+/// * `claim_id` - The ID of the claim. Required by DAO_DAO, ignored by CW_REWARDS and CW20_STAKE.
+///
+/// # Returns
+///
+/// * `StdResult<AuthzMessageType>` - The claim message.
+pub fn claim_msg_type(
+    provider: StakingProvider,
+    claim_contract_address: Addr,
+    claim_id: Option<u64>,
+) -> StdResult<AuthzMessageType> {
+    Ok(AuthzMessageType::ExecuteContract {
+        contract_addr: claim_contract_address,
+        msg_str: claim_msg_str(provider, claim_id)?,
+        funds: vec![],
+    })
+}
+
+/// Constructs a claim message executed by the contract itself, without authz.
+///
+/// Used when the contract holds the position, so the rewards are paid to the contract.
+///
+/// # Arguments
+///
+/// * `provider` - The claim provider (DAO_DAO, CW_REWARDS, CW20_STAKE).
+/// * `claim_contract_address` - The address of the claim contract.
+/// * `claim_id` - The ID of the claim. Required by DAO_DAO, ignored by CW_REWARDS and CW20_STAKE.
+///
+/// # Returns
+///
+/// * `StdResult<CosmosMsg>` - The constructed Wasm claim message.
+pub fn build_direct_claim_msg(
+    provider: StakingProvider,
+    claim_contract_address: Addr,
+    claim_id: Option<u64>,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: claim_contract_address.to_string(),
+        msg: claim_msg_str(provider, claim_id)?.into_bytes().into(),
+        funds: vec![],
+    }))
+}
+
+/// Serializes the claim execute message of a provider.
+fn claim_msg_str(provider: StakingProvider, claim_id: Option<u64>) -> StdResult<String> {
     // Process the claim message within each branch to avoid type mismatch
     let claim_msg_str = match provider {
         StakingProvider::DAO_DAO => {
+            let id = claim_id.ok_or_else(|| {
+                cosmwasm_std::StdError::generic_err("Claim id is required for DAO_DAO claims")
+            })?;
             let claim_msg = ClaimMsgDAODAO {
-                claim: ClaimParamsDAODAO { id: claim_id },
+                claim: ClaimParamsDAODAO { id },
             };
             serde_json::to_string(&claim_msg)
                 .map_err(|e| cosmwasm_std::StdError::generic_err(e.to_string()))?
         }
-        StakingProvider::CW_REWARDS => {
+        StakingProvider::CW_REWARDS | StakingProvider::CW20_STAKE => {
             let claim_msg = ClaimMsgCwRewards {
                 claim_rewards: ClaimParamsCwRewards {},
             };
@@ -61,17 +121,10 @@ pub fn build_claim_msg(
         }
     };
 
-    build_authz_msg(
-        env,
-        user,
-        AuthzMessageType::ExecuteContract {
-            contract_addr: claim_contract_address,
-            msg_str: claim_msg_str,
-            funds: vec![],
-        },
-    )
+    Ok(claim_msg_str)
 }
 
+#[allow(non_snake_case)]
 pub fn build_FIN_claim_msg(env: Env, user: Addr, contract_address: Addr) -> StdResult<CosmosMsg> {
     let claim_msg = serde_json::to_string(&serde_json::json!({ "withdraw_orders": {} }))
         .map_err(|e| cosmwasm_std::StdError::generic_err(e.to_string()))?;
@@ -85,3 +138,67 @@ pub fn build_FIN_claim_msg(env: Env, user: Addr, contract_address: Addr) -> StdR
         },
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_env;
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    fn claim_msg_bytes(provider: StakingProvider, claim_id: Option<u64>) -> StdResult<Vec<u8>> {
+        match build_claim_msg(
+            mock_env(),
+            Addr::unchecked("user"),
+            provider,
+            Addr::unchecked("claim_contract"),
+            claim_id,
+        )? {
+            CosmosMsg::Stargate { value, .. } => Ok(value.to_vec()),
+            msg => panic!("Unexpected message: {:?}", msg),
+        }
+    }
+
+    #[test]
+    fn dao_dao_claim_with_id() {
+        let bytes = claim_msg_bytes(StakingProvider::DAO_DAO, Some(7)).unwrap();
+        assert!(contains(&bytes, br#"{"claim":{"id":7}}"#));
+    }
+
+    #[test]
+    fn dao_dao_claim_without_id_fails() {
+        let err = claim_msg_bytes(StakingProvider::DAO_DAO, None).unwrap_err();
+        assert!(err.to_string().contains("Claim id is required"));
+    }
+
+    #[test]
+    fn cw_rewards_claim_ignores_id() {
+        let without_id = claim_msg_bytes(StakingProvider::CW_REWARDS, None).unwrap();
+        let with_id = claim_msg_bytes(StakingProvider::CW_REWARDS, Some(7)).unwrap();
+        assert!(contains(&without_id, br#"{"claim_rewards":{}}"#));
+        assert_eq!(without_id, with_id);
+    }
+
+    #[test]
+    fn direct_claim_is_plain_wasm_execute() {
+        let msg = build_direct_claim_msg(
+            StakingProvider::DAO_DAO,
+            Addr::unchecked("claim_contract"),
+            Some(7),
+        )
+        .unwrap();
+
+        assert_eq!(
+            msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "claim_contract".to_string(),
+                msg: br#"{"claim":{"id":7}}"#.to_vec().into(),
+                funds: vec![],
+            })
+        );
+    }
+}
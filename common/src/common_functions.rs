@@ -31,8 +31,47 @@ pub fn build_authz_msg(
     user: Addr,
     authz_msg_type: AuthzMessageType,
 ) -> StdResult<CosmosMsg> {
-    // Construct the message to be wrapped in MsgExec
-    let msg_anybuf = match authz_msg_type {
+    build_authz_exec_raw(
+        env.contract.address,
+        build_authz_any(&user, authz_msg_type).into_vec(),
+    )
+}
+
+/// Builds a single Authz message executing several messages on behalf of a user.
+///
+/// The inner messages run in order within one MsgExec, so they either all succeed or all fail.
+///
+/// # Arguments
+///
+/// * `env` - The environment information.
+/// * `user` - The address of the user on whose behalf the actions will be executed.
+/// * `authz_msg_types` - The messages to construct, in execution order.
+///
+/// # Returns
+///
+/// * `StdResult<CosmosMsg>` - The constructed Authz message wrapped in a CosmosMsg.
+pub fn build_authz_msg_batch(
+    env: Env,
+    user: Addr,
+    authz_msg_types: Vec<AuthzMessageType>,
+) -> StdResult<CosmosMsg> {
+    if authz_msg_types.is_empty() {
+        return Err(cosmwasm_std::StdError::generic_err(
+            "An authz batch needs at least one message",
+        ));
+    }
+
+    let inner_anys: Vec<Vec<u8>> = authz_msg_types
+        .into_iter()
+        .map(|authz_msg_type| build_authz_any(&user, authz_msg_type).into_vec())
+        .collect();
+
+    Ok(build_msg_exec(env.contract.address, &inner_anys))
+}
+
+/// Encodes a message as a protobuf `Any` executed by the user.
+fn build_authz_any(user: &Addr, authz_msg_type: AuthzMessageType) -> Anybuf {
+    match authz_msg_type {
         AuthzMessageType::ExecuteContract {
             contract_addr,
             msg_str,
@@ -40,8 +79,8 @@ pub fn build_authz_msg(
         } => {
             // Construct MsgExecuteContract using Anybuf
             let mut execute_contract_buf = Anybuf::new()
-                .append_string(1, &user.to_string()) // sender (field 1)
-                .append_string(2, &contract_addr.to_string()) // contract (field 2)
+                .append_string(1, user.as_str()) // sender (field 1)
+                .append_string(2, contract_addr.as_str()) // contract (field 2)
                 .append_string(3, &msg_str); // msg (field 3)
 
             // Add funds to the message if provided
@@ -51,7 +90,7 @@ pub fn build_authz_msg(
                     .map(|fund| {
                         Anybuf::new()
                             .append_string(1, &fund.denom) // denom (field 1)
-                            .append_string(2, &fund.amount.to_string()) // amount (field 2)
+                            .append_string(2, fund.amount.to_string()) // amount (field 2)
                     })
                     .collect();
 
@@ -63,16 +102,13 @@ pub fn build_authz_msg(
             // Wrap MsgExecuteContract in an Any message
             Anybuf::new()
                 .append_string(1, "/cosmwasm.wasm.v1.MsgExecuteContract") // type_url (field 1)
-                .append_bytes(2, &execute_contract_bytes) // value (field 2)
+                .append_bytes(2, execute_contract_bytes) // value (field 2)
         }
-        AuthzMessageType::Send {
-            to_address,
-            amount,
-        } => {
+        AuthzMessageType::Send { to_address, amount } => {
             // Construct MsgSend using Anybuf
             let mut send_msg_buf = Anybuf::new()
-                .append_string(1, &user.to_string()) // from_address (field 1)
-                .append_string(2, &to_address.to_string()); // to_address (field 2)
+                .append_string(1, user.as_str()) // from_address (field 1)
+                .append_string(2, to_address.as_str()); // to_address (field 2)
 
             // Add amount to the message
             let amount_bufs: Vec<Anybuf> = amount
@@ -80,7 +116,7 @@ pub fn build_authz_msg(
                 .map(|coin| {
                     Anybuf::new()
                         .append_string(1, &coin.denom) // denom (field 1)
-                        .append_string(2, &coin.amount.to_string()) // amount (field 2)
+                        .append_string(2, coin.amount.to_string()) // amount (field 2)
                 })
                 .collect();
 
@@ -91,20 +127,38 @@ pub fn build_authz_msg(
             // Wrap MsgSend in an Any message
             Anybuf::new()
                 .append_string(1, "/cosmos.bank.v1beta1.MsgSend") // type_url (field 1)
-                .append_bytes(2, &send_msg_bytes) // value (field 2)
+                .append_bytes(2, send_msg_bytes) // value (field 2)
         }
-    };
+    }
+}
 
+/// Wraps a pre-encoded `Any` message in an Authz MsgExec.
+///
+/// Lets callers authz-wrap messages not covered by `AuthzMessageType` (IBC transfers, gov votes...).
+///
+/// # Arguments
+///
+/// * `grantee` - The address executing the message on behalf of the granter (usually the contract).
+/// * `inner_any` - The protobuf encoded `Any` (type_url and value) of the message to execute.
+///
+/// # Returns
+///
+/// * `StdResult<CosmosMsg>` - The constructed Authz message wrapped in a CosmosMsg.
+pub fn build_authz_exec_raw(grantee: Addr, inner_any: Vec<u8>) -> StdResult<CosmosMsg> {
+    Ok(build_msg_exec(grantee, &[inner_any]))
+}
+
+/// Wraps encoded `Any` messages in a MsgExec.
+fn build_msg_exec(grantee: Addr, inner_anys: &[Vec<u8>]) -> CosmosMsg {
     // Construct MsgExec using Anybuf
     let msg_exec_buf = Anybuf::new()
-        .append_string(1, &env.contract.address.to_string()) // grantee (field 1)
-        .append_repeated_message(2, &[msg_anybuf]); // msgs (field 2)
+        .append_string(1, grantee.as_str()) // grantee (field 1)
+        .append_repeated_bytes(2, inner_anys); // msgs (field 2)
 
-    let cosmos_msg = CosmosMsg::Stargate {
+    CosmosMsg::Stargate {
         type_url: "/cosmos.authz.v1beta1.MsgExec".to_string(),
         value: msg_exec_buf.as_bytes().into(),
-    };
-    Ok(cosmos_msg)
+    }
 }
 
 pub fn query_token_balance(deps: Deps, address: &Addr, denom: String) -> StdResult<Uint128> {
@@ -116,3 +170,72 @@ pub fn query_token_balance(deps: Deps, address: &Addr, denom: String) -> StdResu
 
     Ok(balance_response.amount.amount)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anybuf::Bufany;
+    use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
+
+    #[test]
+    fn authz_exec_raw_wraps_any() {
+        let inner_any = Anybuf::new()
+            .append_string(1, "/cosmos.gov.v1beta1.MsgVote")
+            .append_bytes(2, Anybuf::new().append_uint64(1, 7).as_bytes())
+            .into_vec();
+
+        let msg = build_authz_exec_raw(Addr::unchecked("grantee"), inner_any.clone()).unwrap();
+
+        let CosmosMsg::Stargate { type_url, value } = msg else {
+            panic!("expected a stargate message");
+        };
+        assert_eq!(type_url, "/cosmos.authz.v1beta1.MsgExec");
+
+        let msg_exec = Bufany::deserialize(value.as_slice()).unwrap();
+        assert_eq!(msg_exec.string(1).unwrap(), "grantee");
+        assert_eq!(msg_exec.repeated_bytes(2).unwrap(), vec![inner_any]);
+    }
+
+    #[test]
+    fn authz_msg_batch_keeps_message_order() {
+        let execute = |contract: &str| AuthzMessageType::ExecuteContract {
+            contract_addr: Addr::unchecked(contract),
+            msg_str: "{}".to_string(),
+            funds: vec![],
+        };
+
+        let msg = build_authz_msg_batch(
+            mock_env(),
+            Addr::unchecked("user"),
+            vec![execute("claim_contract"), execute("stake_contract")],
+        )
+        .unwrap();
+
+        let CosmosMsg::Stargate { type_url, value } = msg else {
+            panic!("expected a stargate message");
+        };
+        assert_eq!(type_url, "/cosmos.authz.v1beta1.MsgExec");
+
+        let msg_exec = Bufany::deserialize(value.as_slice()).unwrap();
+        assert_eq!(msg_exec.string(1).unwrap(), MOCK_CONTRACT_ADDR);
+        let contracts: Vec<String> = msg_exec
+            .repeated_bytes(2)
+            .unwrap()
+            .iter()
+            .map(|inner_any| {
+                let any = Bufany::deserialize(inner_any).unwrap();
+                assert_eq!(
+                    any.string(1).unwrap(),
+                    "/cosmwasm.wasm.v1.MsgExecuteContract"
+                );
+                let execute_contract_bytes = any.bytes(2).unwrap();
+                let execute_contract = Bufany::deserialize(&execute_contract_bytes).unwrap();
+                assert_eq!(execute_contract.string(1).unwrap(), "user");
+                execute_contract.string(2).unwrap()
+            })
+            .collect();
+        assert_eq!(contracts, vec!["claim_contract", "stake_contract"]);
+
+        assert!(build_authz_msg_batch(mock_env(), Addr::unchecked("user"), vec![]).is_err());
+    }
+}
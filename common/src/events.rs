@@ -0,0 +1,199 @@
+use cosmwasm_std::{Coin, Event, StdError, StdResult, SubMsgResult, Uint128};
+use std::fmt::Debug;
+
+/// Maximum length of a debug-formatted attribute value, kept well below the chain's attribute size limit.
+pub const MAX_DEBUG_ATTRIBUTE_LEN: usize = 2048;
+
+/// Result of an action, reported in the `result` attribute of an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionResult {
+    Ok,
+    Failed,
+    SkippedDust,
+}
+
+impl ActionResult {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActionResult::Ok => "ok",
+            ActionResult::Failed => "failed",
+            ActionResult::SkippedDust => "skipped_dust",
+        }
+    }
+}
+
+/// Builds an `autorujira.<namespace>` event tagged with the given action.
+pub fn action_event(namespace: &str, action: &str) -> Event {
+    Event::new(format!("autorujira.{}", namespace)).add_attribute("action", action)
+}
+
+/// Appends the `result` attribute to an event.
+pub fn add_result(event: Event, result: ActionResult) -> Event {
+    event.add_attribute("result", result.as_str())
+}
+
+/// Marks an event as failed and appends the error message.
+pub fn add_error(event: Event, error: impl Into<String>) -> Event {
+    add_result(event, ActionResult::Failed).add_attribute("error", error)
+}
+
+/// Appends a debug-formatted attribute, truncated to `MAX_DEBUG_ATTRIBUTE_LEN`.
+///
+/// A `truncated=true` attribute is appended when the value had to be cut.
+pub fn add_debug_attribute(event: Event, key: &str, value: &impl Debug) -> Event {
+    let mut formatted = format!("{:?}", value);
+    if formatted.len() <= MAX_DEBUG_ATTRIBUTE_LEN {
+        return event.add_attribute(key, formatted);
+    }
+
+    let mut end = MAX_DEBUG_ATTRIBUTE_LEN;
+    while !formatted.is_char_boundary(end) {
+        end -= 1;
+    }
+    formatted.truncate(end);
+
+    event
+        .add_attribute(key, formatted)
+        .add_attribute("truncated", "true")
+}
+
+/// Sums the coins credited to `recipient` by the bank `transfer` events of a submessage.
+///
+/// Lets replies see every denom a message paid out, not only the denom they expected.
+pub fn coins_received(events: &[Event], recipient: &str) -> StdResult<Vec<Coin>> {
+    let mut received: Vec<Coin> = vec![];
+
+    for event in events.iter().filter(|event| event.ty == "transfer") {
+        let attribute = |key: &str| {
+            event
+                .attributes
+                .iter()
+                .find(|attribute| attribute.key == key)
+                .map(|attribute| attribute.value.as_str())
+        };
+        if attribute("recipient") != Some(recipient) {
+            continue;
+        }
+
+        for coin in parse_coins(attribute("amount").unwrap_or_default())? {
+            match received.iter_mut().find(|total| total.denom == coin.denom) {
+                Some(total) => total.amount += coin.amount,
+                None => received.push(coin),
+            }
+        }
+    }
+
+    Ok(received)
+}
+
+/// Parses a comma separated list of coins as printed in events, e.g. `1000ukuji,5factory/x/y`.
+fn parse_coins(value: &str) -> StdResult<Vec<Coin>> {
+    value
+        .split(',')
+        .filter(|coin| !coin.is_empty())
+        .map(|coin| {
+            let split = coin
+                .find(|c: char| !c.is_ascii_digit())
+                .filter(|split| *split > 0)
+                .ok_or_else(|| StdError::generic_err(format!("Invalid coin: {}", coin)))?;
+            let (amount, denom) = coin.split_at(split);
+            Ok(Coin {
+                denom: denom.to_string(),
+                amount: amount.parse::<Uint128>()?,
+            })
+        })
+        .collect()
+}
+
+/// Appends the result of a submessage, including the error message when it failed.
+pub fn add_submsg_result(event: Event, result: &SubMsgResult) -> Event {
+    match result {
+        SubMsgResult::Ok(_) => add_result(event, ActionResult::Ok),
+        SubMsgResult::Err(err) => add_error(event, err.as_str()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{Attribute, SubMsgResponse};
+
+    #[test]
+    fn action_event_shape() {
+        let event = action_event("autoclaimer", "claim");
+        assert_eq!(event.ty, "autorujira.autoclaimer");
+        assert_eq!(event.attributes, vec![Attribute::new("action", "claim")]);
+    }
+
+    #[test]
+    fn debug_attribute_truncation() {
+        let short = add_debug_attribute(action_event("autoclaimer", "claim"), "pairs", &vec![1, 2]);
+        assert_eq!(short.attributes[1], Attribute::new("pairs", "[1, 2]"));
+        assert_eq!(short.attributes.len(), 2);
+
+        let long = add_debug_attribute(
+            action_event("autoclaimer", "claim"),
+            "pairs",
+            &vec!["é"; MAX_DEBUG_ATTRIBUTE_LEN],
+        );
+        assert!(long.attributes[1].value.len() <= MAX_DEBUG_ATTRIBUTE_LEN);
+        assert_eq!(long.attributes[2], Attribute::new("truncated", "true"));
+    }
+
+    #[test]
+    fn coins_received_from_transfers() {
+        let transfer = |recipient: &str, amount: &str| {
+            Event::new("transfer")
+                .add_attribute("recipient", recipient)
+                .add_attribute("sender", "claim_contract")
+                .add_attribute("amount", amount)
+        };
+        let events = vec![
+            transfer("user", "1000token1"),
+            transfer("other", "7token1"),
+            Event::new("wasm").add_attribute("recipient", "user"),
+            transfer("user", "5token1,20factory/addr/token2"),
+        ];
+
+        assert_eq!(
+            coins_received(&events, "user").unwrap(),
+            vec![
+                Coin::new(1005, "token1"),
+                Coin::new(20, "factory/addr/token2"),
+            ]
+        );
+        assert!(coins_received(&events, "nobody").unwrap().is_empty());
+        assert!(coins_received(&[transfer("user", "token1")], "user").is_err());
+    }
+
+    #[test]
+    fn submsg_result_attributes() {
+        let ok = add_submsg_result(
+            action_event("autoclaimer", "stake"),
+            &SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        );
+        assert_eq!(
+            ok.attributes,
+            vec![
+                Attribute::new("action", "stake"),
+                Attribute::new("result", "ok"),
+            ]
+        );
+
+        let failed = add_submsg_result(
+            action_event("autoclaimer", "stake"),
+            &SubMsgResult::Err("out of gas".to_string()),
+        );
+        assert_eq!(
+            failed.attributes,
+            vec![
+                Attribute::new("action", "stake"),
+                Attribute::new("result", "failed"),
+                Attribute::new("error", "out of gas"),
+            ]
+        );
+    }
+}
@@ -1,5 +1,7 @@
-pub mod common_functions;
-pub mod staking_provider;
 pub mod claim;
+pub mod common_functions;
+pub mod events;
+pub mod send;
 pub mod stake;
-pub mod send;
\ No newline at end of file
+pub mod staking_provider;
+pub mod swap;
@@ -27,7 +27,7 @@ pub fn build_send_msg(
         AuthzMessageType::Send {
             to_address,
             amount: vec![Coin {
-                denom: denom,
+                denom,
                 amount: amount.into(),
             }],
         },
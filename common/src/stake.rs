@@ -1,6 +1,9 @@
-use cosmwasm_std::{Addr, Coin, CosmosMsg, Env, StdResult};
+use crate::{
+    common_functions::{build_authz_msg, AuthzMessageType},
+    staking_provider::StakingProvider,
+};
+use cosmwasm_std::{to_json_binary, Addr, Binary, Coin, CosmosMsg, Env, StdResult, Uint128};
 use serde::{Deserialize, Serialize};
-use crate::{common_functions::{build_authz_msg, AuthzMessageType}, staking_provider::StakingProvider};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -8,16 +11,27 @@ pub enum StakeContractExecuteMsg {
     Stake {},
 }
 
+/// The CW20 `Send` message, which transfers tokens to a contract and calls its receive hook.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20ExecuteMsg {
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+}
+
 /// Constructs an Authz message to stake tokens depending on the provider.
 ///
 /// # Arguments
 ///
 /// * `env` - The environment information.
 /// * `user` - The address of the user who will stake the tokens.
-/// * `provider` - The staking provider (DAO_DAO, CW_REWARDS).
+/// * `provider` - The staking provider (DAO_DAO, CW_REWARDS, CW20_STAKE).
 /// * `stake_contract_address` - The address of the stake contract.
 /// * `amount` - The amount to stake.
-/// * `denom` - The denomination of the token to stake.
+/// * `denom` - The denomination of the token to stake, or the CW20 contract address for CW20_STAKE.
 ///
 /// # Returns
 ///
@@ -30,6 +44,31 @@ pub fn build_stake_msg(
     amount: u128,
     denom: String,
 ) -> StdResult<CosmosMsg> {
+    build_authz_msg(
+        env,
+        user,
+        stake_msg_type(provider, stake_contract_address, amount, denom)?,
+    )
+}
+
+/// Constructs the stake message of a provider, to be wrapped in an Authz message.
+///
+/// # Arguments
+///
+/// * `provider` - The staking provider (DAO_DAO, CW_REWARDS, CW20_STAKE).
+/// * `stake_contract_address` - The address of the stake contract.
+/// * `amount` - The amount to stake.
+/// * `denom` - The denomination of the token to stake, or the CW20 contract address for CW20_STAKE.
+///
+/// # Returns
+///
+/// * `StdResult<AuthzMessageType>` - The stake message.
+pub fn stake_msg_type(
+    provider: StakingProvider,
+    stake_contract_address: Addr,
+    amount: u128,
+    denom: String,
+) -> StdResult<AuthzMessageType> {
     match provider {
         StakingProvider::DAO_DAO | StakingProvider::CW_REWARDS => {
             let stake_msg = StakeContractExecuteMsg::Stake {};
@@ -41,16 +80,144 @@ pub fn build_stake_msg(
                 amount: amount.into(),
             }];
 
-            // Build the actual message, using a common function or direct construction
-            build_authz_msg(
-                env,
-                user,
-                AuthzMessageType::ExecuteContract {
-                    contract_addr: stake_contract_address,
-                    msg_str: stake_msg_str,
-                    funds,
-                },
+            Ok(AuthzMessageType::ExecuteContract {
+                contract_addr: stake_contract_address,
+                msg_str: stake_msg_str,
+                funds,
+            })
+        }
+        StakingProvider::CW20_STAKE => cw20_stake_msg_type(
+            Addr::unchecked(denom),
+            stake_contract_address,
+            amount,
+            to_json_binary(&StakeContractExecuteMsg::Stake {})?,
+        ),
+    }
+}
+
+/// Constructs an Authz message to stake CW20 tokens through a `Send` with a stake hook.
+///
+/// # Arguments
+///
+/// * `env` - The environment information.
+/// * `user` - The address of the user who will stake the tokens.
+/// * `cw20_contract` - The address of the CW20 token contract.
+/// * `stake_contract` - The address of the stake contract receiving the tokens.
+/// * `amount` - The amount to stake.
+/// * `hook_msg` - The message the stake contract receives along with the tokens.
+///
+/// # Returns
+///
+/// * `StdResult<CosmosMsg>` - The constructed Authz stake message.
+pub fn build_cw20_stake_msg(
+    env: Env,
+    user: Addr,
+    cw20_contract: Addr,
+    stake_contract: Addr,
+    amount: u128,
+    hook_msg: Binary,
+) -> StdResult<CosmosMsg> {
+    build_authz_msg(
+        env,
+        user,
+        cw20_stake_msg_type(cw20_contract, stake_contract, amount, hook_msg)?,
+    )
+}
+
+/// Constructs the CW20 `Send` with a stake hook, to be wrapped in an Authz message.
+fn cw20_stake_msg_type(
+    cw20_contract: Addr,
+    stake_contract: Addr,
+    amount: u128,
+    hook_msg: Binary,
+) -> StdResult<AuthzMessageType> {
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: stake_contract.to_string(),
+        amount: amount.into(),
+        msg: hook_msg,
+    };
+    let send_msg_str = serde_json::to_string(&send_msg)
+        .map_err(|e| cosmwasm_std::StdError::generic_err(e.to_string()))?;
+
+    Ok(AuthzMessageType::ExecuteContract {
+        contract_addr: cw20_contract,
+        msg_str: send_msg_str,
+        funds: vec![],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anybuf::Bufany;
+    use cosmwasm_std::{from_json, testing::mock_env};
+
+    #[test]
+    fn stake_msg_is_authz_exec() {
+        for provider in [StakingProvider::DAO_DAO, StakingProvider::CW_REWARDS] {
+            let msg = build_stake_msg(
+                mock_env(),
+                Addr::unchecked("user"),
+                provider,
+                Addr::unchecked("stake_contract"),
+                1000,
+                "ukuji".to_string(),
             )
+            .unwrap();
+
+            let CosmosMsg::Stargate { type_url, value } = msg else {
+                panic!("expected a stargate message");
+            };
+            assert_eq!(type_url, "/cosmos.authz.v1beta1.MsgExec");
+
+            let msg_exec = Bufany::deserialize(value.as_slice()).unwrap();
+            let inner_anys = msg_exec.repeated_bytes(2).unwrap();
+            let any = Bufany::deserialize(&inner_anys[0]).unwrap();
+            assert_eq!(
+                any.string(1).unwrap(),
+                "/cosmwasm.wasm.v1.MsgExecuteContract"
+            );
+            let execute_contract_bytes = any.bytes(2).unwrap();
+            let execute_contract = Bufany::deserialize(&execute_contract_bytes).unwrap();
+            assert_eq!(execute_contract.string(2).unwrap(), "stake_contract");
+            assert_eq!(execute_contract.string(3).unwrap(), r#"{"stake":{}}"#);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn cw20_stake_sends_to_stake_contract_with_hook() {
+        let hook_msg = to_json_binary(&StakeContractExecuteMsg::Stake {}).unwrap();
+        let msg = build_cw20_stake_msg(
+            mock_env(),
+            Addr::unchecked("user"),
+            Addr::unchecked("cw20_contract"),
+            Addr::unchecked("stake_contract"),
+            1000,
+            hook_msg.clone(),
+        )
+        .unwrap();
+
+        let CosmosMsg::Stargate { value, .. } = msg else {
+            panic!("expected a stargate message");
+        };
+        let msg_exec = Bufany::deserialize(value.as_slice()).unwrap();
+        let inner_anys = msg_exec.repeated_bytes(2).unwrap();
+        let any = Bufany::deserialize(&inner_anys[0]).unwrap();
+        let execute_contract_bytes = any.bytes(2).unwrap();
+        let execute_contract = Bufany::deserialize(&execute_contract_bytes).unwrap();
+        assert_eq!(execute_contract.string(1).unwrap(), "user");
+        assert_eq!(execute_contract.string(2).unwrap(), "cw20_contract");
+        assert!(execute_contract.repeated_bytes(5).unwrap().is_empty());
+
+        let send_msg: Cw20ExecuteMsg =
+            from_json(execute_contract.string(3).unwrap().as_bytes()).unwrap();
+        assert_eq!(
+            send_msg,
+            Cw20ExecuteMsg::Send {
+                contract: "stake_contract".to_string(),
+                amount: Uint128::new(1000),
+                msg: hook_msg,
+            }
+        );
+    }
+}
@@ -2,9 +2,11 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[allow(non_camel_case_types)]
 pub enum StakingProvider {
     DAO_DAO,
     CW_REWARDS,
+    CW20_STAKE,
 }
 
 impl std::str::FromStr for StakingProvider {
@@ -14,6 +16,7 @@ impl std::str::FromStr for StakingProvider {
         match input {
             "CW_REWARDS" => Ok(StakingProvider::CW_REWARDS),
             "DAO_DAO" => Ok(StakingProvider::DAO_DAO),
+            "CW20_STAKE" => Ok(StakingProvider::CW20_STAKE),
             _ => Err(()),
         }
     }
@@ -0,0 +1,85 @@
+use crate::common_functions::{build_authz_msg, AuthzMessageType};
+use cosmwasm_std::{Addr, Coin, CosmosMsg, Env, StdResult, Uint128};
+
+/// Constructs an Authz message to swap tokens on a FIN market.
+///
+/// # Arguments
+///
+/// * `env` - The environment information.
+/// * `user` - The address of the user who will swap the tokens.
+/// * `contract_address` - The address of the FIN market contract.
+/// * `amount` - The amount to swap.
+/// * `denom` - The denomination of the token to swap.
+/// * `min_return` - The minimum amount returned, the swap fails below it.
+///
+/// # Returns
+///
+/// * `StdResult<CosmosMsg>` - The constructed Authz swap message.
+#[allow(non_snake_case)]
+pub fn build_FIN_swap_msg(
+    env: Env,
+    user: Addr,
+    contract_address: Addr,
+    amount: u128,
+    denom: String,
+    min_return: Option<Uint128>,
+) -> StdResult<CosmosMsg> {
+    let swap = match min_return {
+        Some(min_return) => serde_json::json!({ "swap": { "min_return": min_return } }),
+        None => serde_json::json!({ "swap": {} }),
+    };
+    let swap_msg = serde_json::to_string(&swap)
+        .map_err(|e| cosmwasm_std::StdError::generic_err(e.to_string()))?;
+    build_authz_msg(
+        env,
+        user,
+        AuthzMessageType::ExecuteContract {
+            contract_addr: contract_address,
+            msg_str: swap_msg,
+            funds: vec![Coin {
+                denom,
+                amount: amount.into(),
+            }],
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_env;
+
+    fn swap_msg_bytes(min_return: Option<Uint128>) -> Vec<u8> {
+        match build_FIN_swap_msg(
+            mock_env(),
+            Addr::unchecked("user"),
+            Addr::unchecked("fin_market"),
+            1000,
+            "token1".to_string(),
+            min_return,
+        )
+        .unwrap()
+        {
+            CosmosMsg::Stargate { value, .. } => value.to_vec(),
+            msg => panic!("Unexpected message: {:?}", msg),
+        }
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    #[test]
+    fn swap_with_min_return() {
+        let bytes = swap_msg_bytes(Some(Uint128::new(990)));
+        assert!(contains(&bytes, br#"{"swap":{"min_return":"990"}}"#));
+    }
+
+    #[test]
+    fn swap_without_min_return() {
+        let bytes = swap_msg_bytes(None);
+        assert!(contains(&bytes, br#"{"swap":{}}"#));
+    }
+}